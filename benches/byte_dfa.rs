@@ -0,0 +1,67 @@
+//! Compares stepping a [`ByteDFA`] with stepping the [`DFA`] it was built from.
+//!
+//! Run with `cargo bench --bench byte_dfa`. It fails unless the byte table is at least
+//! [`MIN_SPEEDUP`] times as fast, taking the best of several runs of each to keep other load
+//! on the machine from skewing the comparison.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use fsm::{byte_dfa::ByteDFA, dfa::DFA};
+
+/// How many times faster than the hash map lookups the byte table must be.
+const MIN_SPEEDUP: f64 = 3.0;
+
+const RUNS: usize = 10;
+
+/// Accepts inputs with an even number of zero bytes, rejecting any byte above 'z'.
+fn even_zeros() -> DFA<u8, bool> {
+    let mut dfa = DFA::new(true);
+    dfa.add_state(false);
+    for byte in 0..=b'z' {
+        let (even, odd) = if byte == 0 { (1, 0) } else { (0, 1) };
+        dfa.get_state_mut(0).unwrap().set_transition(byte, even);
+        dfa.get_state_mut(1).unwrap().set_transition(byte, odd);
+    }
+    dfa
+}
+
+fn best_of<T>(mut run: impl FnMut() -> T) -> (Duration, T) {
+    let mut best = Duration::MAX;
+    let mut result = None;
+    for _ in 0..RUNS {
+        let timer = Instant::now();
+        let value = black_box(run());
+        best = best.min(timer.elapsed());
+        result = Some(value);
+    }
+    (best, result.unwrap())
+}
+
+fn main() {
+    let dfa = even_zeros();
+    let input: Vec<u8> = (0..1 << 22).map(|i| (i % 97) as u8).collect();
+
+    let (generic_time, generic) =
+        best_of(|| dfa.traverse(black_box(&input).iter()).map(|st| st.index()));
+    let bytes = ByteDFA::from(dfa);
+    let (specialized_time, specialized) = best_of(|| bytes.traverse(black_box(&input)));
+    assert_eq!(generic, specialized);
+
+    let speedup = generic_time.as_secs_f64() / specialized_time.as_secs_f64();
+    println!(
+        "DFA: {:?}, ByteDFA: {:?}, {:.1}x faster over {} bytes",
+        generic_time,
+        specialized_time,
+        speedup,
+        input.len()
+    );
+    assert!(
+        speedup >= MIN_SPEEDUP,
+        "ByteDFA is only {:.1}x faster than DFA, expected at least {}x",
+        speedup,
+        MIN_SPEEDUP
+    );
+}
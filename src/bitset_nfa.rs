@@ -0,0 +1,180 @@
+use alloc::{vec, vec::Vec};
+use core::{fmt::Display, hash::Hash};
+
+use crate::{collections::HashMap, nfa::NFA};
+
+/// The most states an NFA can have and still be compiled into a [`BitsetNFA`].
+pub const MAX_STATES: usize = 128;
+
+/// An error for an NFA with more states than fit in a bitset.
+#[derive(Debug, PartialEq)]
+pub struct TooManyStatesError {
+    /// The number of states the NFA has.
+    pub states: usize,
+}
+
+impl Display for TooManyStatesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "NFA has {} states, but at most {} fit in a bitset",
+            self.states, MAX_STATES
+        )
+    }
+}
+
+/// An NFA of at most [`MAX_STATES`] states, simulated with bitwise operations.
+///
+/// A set of states is a `u128` whose bit `i` is set if state `i` is in it. For every symbol,
+/// each state has a mask of the states that symbol leads to, so stepping a set along a symbol
+/// is the union of the masks of its states.
+#[derive(Clone, Debug)]
+pub struct BitsetNFA<L> {
+    starts: u128,
+    finishes: u128,
+    masks: HashMap<L, Vec<u128>>,
+}
+
+impl<L> BitsetNFA<L>
+where
+    L: Eq + Hash,
+{
+    pub(crate) fn from_nfa<S>(nfa: NFA<L, S>) -> Result<Self, TooManyStatesError> {
+        let len = nfa.states.len();
+        if len > MAX_STATES {
+            return Err(TooManyStatesError { states: len });
+        }
+
+        let mut bitset = BitsetNFA {
+            starts: nfa.starts.iter().fold(0, |set, &start| set | 1 << start),
+            finishes: 0,
+            masks: HashMap::new(),
+        };
+
+        for (i, state) in nfa.states.into_iter().enumerate() {
+            if state.is_finish() {
+                bitset.finishes |= 1 << i;
+            }
+            for transition in state.transitions {
+                let masks = bitset
+                    .masks
+                    .entry(transition.symbol)
+                    .or_insert_with(|| vec![0; len]);
+                masks[i] |= 1 << transition.dest;
+            }
+        }
+
+        Ok(bitset)
+    }
+
+    /// Gets the set of states reached from `states` on the given symbol.
+    pub fn step(&self, states: u128, symbol: &L) -> u128 {
+        let Some(masks) = self.masks.get(symbol) else {
+            return 0;
+        };
+
+        let mut next = 0;
+        let mut rest = states;
+        while rest != 0 {
+            next |= masks[rest.trailing_zeros() as usize];
+            // clear the lowest set bit
+            rest &= rest - 1;
+        }
+        next
+    }
+
+    /// Gets the set of states reached from the start states on the given symbols.
+    pub fn traverse<'a, I>(&self, symbols: I) -> u128
+    where
+        I: IntoIterator<Item = &'a L>,
+        L: 'a,
+    {
+        let mut states = self.starts;
+        for symbol in symbols {
+            if states == 0 {
+                break;
+            }
+            states = self.step(states, symbol);
+        }
+        states
+    }
+
+    /// Returns whether some finish state is reached from the start states on the given symbols.
+    pub fn accepts<'a, I>(&self, symbols: I) -> bool
+    where
+        I: IntoIterator<Item = &'a L>,
+        L: 'a,
+    {
+        self.traverse(symbols) & self.finishes != 0
+    }
+}
+
+impl<L> BitsetNFA<L> {
+    /// Gets the set of start states.
+    pub fn starts(&self) -> u128 {
+        self.starts
+    }
+
+    /// Gets the set of finish states.
+    pub fn finishes(&self) -> u128 {
+        self.finishes
+    }
+}
+
+#[test]
+fn test_bitset_nfa() {
+    // strings over {a, b} whose second-to-last symbol is 'a', with an extra start state
+    // that only accepts "c"
+    let nfa = nfa! {
+        start: 0, 3;
+        states: { 0 => false, 1 => false, 2 => true, 3 => false };
+        edges: {
+            0 -'a'-> 0, 0 -'b'-> 0, 0 -'a'-> 1,
+            1 -'a'-> 2, 1 -'b'-> 2,
+            3 -'c'-> 2
+        }
+    };
+    let bitset = nfa.clone().compile_bitset().unwrap();
+    assert_eq!(bitset.starts(), 0b1001);
+    assert_eq!(bitset.finishes(), 0b0100);
+
+    // every string of up to four symbols
+    let mut inputs = vec![Vec::new()];
+    let mut i = 0;
+    while i < inputs.len() {
+        if inputs[i].len() < 4 {
+            for c in "abc".chars() {
+                let mut input = inputs[i].clone();
+                input.push(c);
+                inputs.push(input);
+            }
+        }
+        i += 1;
+    }
+    for input in inputs.iter() {
+        assert_eq!(
+            bitset.accepts(input.iter()),
+            nfa.accepts(input.iter()),
+            "{:?}",
+            input
+        );
+    }
+    assert!(bitset.accepts(['b', 'a', 'b'].iter()));
+    assert!(bitset.accepts(['c'].iter()));
+    assert_eq!(bitset.traverse(['x'].iter()), 0);
+
+    use crate::{nfa::NFABuilder, state::State};
+
+    let mut builder = NFABuilder::default();
+    for _ in 0..=MAX_STATES {
+        builder.add_state(State::new(true, ()));
+    }
+    builder.set_start(0);
+    let large: NFA<char, ()> = builder.build().unwrap();
+    assert_eq!(
+        large.compile_bitset().err(),
+        Some(TooManyStatesError {
+            states: MAX_STATES + 1
+        })
+    );
+}
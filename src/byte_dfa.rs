@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+
+use crate::dfa::DFA;
+
+/// A DFA over bytes, storing a full table of 256 transitions per state so that every step is
+/// a single array lookup.
+pub struct ByteDFA<S> {
+    values: Vec<S>,
+    transitions: Vec<[Option<usize>; 256]>,
+    start: usize,
+}
+
+impl<S, H> From<DFA<u8, S, H>> for ByteDFA<S> {
+    fn from(dfa: DFA<u8, S, H>) -> Self {
+        let state_count = dfa.values.len();
+        let transitions = dfa
+            .transitions
+            .iter()
+            .map(|transitions| {
+                let mut table = [None; 256];
+                for (&byte, &dest) in transitions.iter() {
+                    // dangling transitions lead nowhere, as in `DFA::traverse`
+                    if dest < state_count {
+                        table[byte as usize] = Some(dest);
+                    }
+                }
+                table
+            })
+            .collect();
+
+        Self {
+            values: dfa.values,
+            transitions,
+            start: dfa.start,
+        }
+    }
+}
+
+impl<S> ByteDFA<S> {
+    /// Returns the number of states.
+    pub fn state_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the index of the start state.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the value of the state at the given index.
+    pub fn get_value(&self, index: usize) -> Option<&S> {
+        self.values.get(index)
+    }
+
+    /// Returns the state reached from `from` on the given byte, if there is a transition.
+    pub fn next(&self, from: usize, byte: u8) -> Option<usize> {
+        self.transitions.get(from)?[byte as usize]
+    }
+
+    /// Returns the index of the state reached by the input, or `None` if some byte has no
+    /// transition.
+    pub fn traverse(&self, input: &[u8]) -> Option<usize> {
+        let mut curr = self.start;
+        for &byte in input {
+            curr = self.transitions[curr][byte as usize]?;
+        }
+        Some(curr)
+    }
+
+    /// Returns whether the state reached by the input satisfies `is_accepting`.
+    pub fn accepts(&self, input: &[u8], is_accepting: impl Fn(&S) -> bool) -> bool {
+        self.traverse(input)
+            .is_some_and(|index| is_accepting(&self.values[index]))
+    }
+}
+
+#[cfg(test)]
+fn even_zeros() -> DFA<u8, bool> {
+    // accepts inputs with an even number of zero bytes, rejecting any byte above 'z'
+    let mut dfa = DFA::new(true);
+    dfa.add_state(false);
+    for byte in 0..=b'z' {
+        let (even, odd) = if byte == 0 { (1, 0) } else { (0, 1) };
+        dfa.get_state_mut(0).unwrap().set_transition(byte, even);
+        dfa.get_state_mut(1).unwrap().set_transition(byte, odd);
+    }
+    dfa
+}
+
+#[test]
+fn test_byte_dfa_matches_dfa() {
+    let dfa = even_zeros();
+    let inputs: [&[u8]; 6] = [b"", b"\0", b"a\0b\0", b"\0\0\0", b"abc{", b"\0~"];
+    let expected: Vec<Option<usize>> = inputs
+        .iter()
+        .map(|input| dfa.traverse(input.iter()).map(|st| st.index()))
+        .collect();
+
+    let bytes = ByteDFA::from(dfa);
+    assert_eq!(bytes.state_count(), 2);
+    assert_eq!(
+        inputs
+            .iter()
+            .map(|input| bytes.traverse(input))
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert!(bytes.accepts(b"a\0b\0", |&v| v));
+    assert!(!bytes.accepts(b"\0", |&v| v));
+    assert_eq!(bytes.next(0, b'{'), None);
+}
+
+#[test]
+fn test_byte_dfa_long_input() {
+    let dfa = even_zeros();
+    let input: Vec<u8> = (0..1 << 20).map(|i| (i % 97) as u8).collect();
+
+    let generic = dfa.traverse(input.iter()).map(|st| st.index());
+    assert_eq!(ByteDFA::from(dfa).traverse(&input), generic);
+}
@@ -0,0 +1,14 @@
+//! The hash maps used throughout the crate, which come from the standard library when the `std`
+//! feature is enabled and from `hashbrown` otherwise.
+
+#[cfg(feature = "std")]
+pub use std::collections::{hash_map::RandomState, HashMap, HashSet};
+
+#[cfg(not(feature = "std"))]
+pub use hashbrown::DefaultHashBuilder as RandomState;
+
+#[cfg(not(feature = "std"))]
+pub type HashMap<K, V, S = RandomState> = hashbrown::HashMap<K, V, S>;
+
+#[cfg(not(feature = "std"))]
+pub type HashSet<T, S = RandomState> = hashbrown::HashSet<T, S>;
@@ -0,0 +1,4136 @@
+use alloc::{
+    collections::VecDeque,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    borrow::Borrow,
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    collections::{HashMap, HashSet, RandomState},
+    dot::DotWriter,
+    graph,
+    nfa::NFA,
+    stats::AutomatonStats,
+    transition::RealTransition,
+};
+
+/// A deterministic finite automaton.
+///
+/// Every state holds a value of type `S` and at most one transition per symbol. The
+/// transition maps hash symbols with `H`, which can be swapped for a faster hasher.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "L: serde::Serialize + Eq + Hash, S: serde::Serialize, H: BuildHasher"
+    ))
+)]
+pub struct DFA<L, S, H = RandomState> {
+    pub(crate) values: Vec<S>,
+    pub(crate) transitions: Vec<HashMap<L, usize, H>>,
+    pub(crate) start: usize,
+}
+
+/// The fields of a serialized DFA, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(
+    deserialize = "L: serde::Deserialize<'de> + Eq + Hash, S: serde::Deserialize<'de>, \
+                   H: BuildHasher + Default"
+))]
+struct RawDFA<L, S, H> {
+    values: Vec<S>,
+    transitions: Vec<HashMap<L, usize, H>>,
+    start: usize,
+}
+
+/// Deserializing fails unless there is a state, the start index refers to one, and there is
+/// a transition map for every state. Transitions to states that do not exist are kept, as
+/// they are in a DFA built by hand.
+#[cfg(feature = "serde")]
+impl<'de, L, S, H> serde::Deserialize<'de> for DFA<L, S, H>
+where
+    L: serde::Deserialize<'de> + Eq + Hash,
+    S: serde::Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawDFA {
+            values,
+            transitions,
+            start,
+        } = RawDFA::deserialize(deserializer)?;
+
+        let error = if values.is_empty() {
+            Some(BuildError::MissingStates)
+        } else if start >= values.len() {
+            Some(BuildError::InvalidStartIndex(start))
+        } else if transitions.len() != values.len() {
+            Some(BuildError::MismatchedTransitions {
+                states: values.len(),
+                maps: transitions.len(),
+            })
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            return Err(serde::de::Error::custom(error));
+        }
+
+        Ok(DFA {
+            values,
+            transitions,
+            start,
+        })
+    }
+}
+
+/// DFAs are equal when their states, transitions, and start state are identical, index for
+/// index.
+impl<L, S, H> PartialEq for DFA<L, S, H>
+where
+    L: Eq + Hash,
+    S: PartialEq,
+    H: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start
+            && self.values == other.values
+            && self.transitions == other.transitions
+    }
+}
+
+/// A DFA whose symbols are reference counted.
+///
+/// Products and completion clone symbols into the DFAs they build. With shared
+/// symbols each clone only bumps a count, which is much cheaper for symbols like `String`.
+/// Traversing one takes inputs of `Rc<L>` as well.
+pub type SharedDFA<L, S, H = RandomState> = DFA<Rc<L>, S, H>;
+
+/// Yields the value and transitions of every state, in order of index.
+impl<L, S, H> IntoIterator for DFA<L, S, H> {
+    type Item = (S, HashMap<L, usize, H>);
+    type IntoIter = core::iter::Zip<vec::IntoIter<S>, vec::IntoIter<HashMap<L, usize, H>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter().zip(self.transitions)
+    }
+}
+
+/// An error for a state that would have transitions to different states on the same symbol.
+#[derive(Debug, PartialEq)]
+pub struct DeterminismError {
+    /// The index of the conflicting state.
+    pub state: usize,
+}
+
+impl Display for DeterminismError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "state {} has conflicting transitions", self.state)
+    }
+}
+
+/// An error for a DFA whose transitions form a cycle, so its states have no topological order.
+#[derive(Debug, PartialEq)]
+pub struct CycleError {
+    /// The index of a state on the cycle.
+    pub state: usize,
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "state {} is on a cycle", self.state)
+    }
+}
+
+/// An error for a DFA assembled from parts that do not describe a valid DFA.
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// There are no states.
+    MissingStates,
+    /// The start index does not refer to a state.
+    InvalidStartIndex(usize),
+    /// A transition starts or ends at an index that does not refer to a state.
+    InvalidTransitionTarget(usize),
+    /// A state has transitions to different states on the same symbol.
+    Nondeterministic(DeterminismError),
+    /// The number of transition maps is not the number of states.
+    MismatchedTransitions {
+        /// The number of states.
+        states: usize,
+        /// The number of transition maps.
+        maps: usize,
+    },
+    /// A state has an output or weight for a symbol it has no transition on, or lacks a weight
+    /// for one of its transitions.
+    MismatchedAnnotations(usize),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MissingStates => write!(f, "must have at least one state"),
+            Self::InvalidStartIndex(start) => write!(f, "start index {} must be valid", start),
+            Self::InvalidTransitionTarget(index) => {
+                write!(f, "transition endpoint {} must be a valid state", index)
+            }
+            Self::Nondeterministic(err) => write!(f, "{}", err),
+            Self::MismatchedTransitions { states, maps } => {
+                write!(f, "found {} transition maps for {} states", maps, states)
+            }
+            Self::MismatchedAnnotations(state) => {
+                write!(
+                    f,
+                    "annotations of state {} must match its transitions",
+                    state
+                )
+            }
+        }
+    }
+}
+
+impl From<DeterminismError> for BuildError {
+    fn from(err: DeterminismError) -> Self {
+        Self::Nondeterministic(err)
+    }
+}
+
+/// A DFA with accepting flags, flattened into plain data.
+///
+/// Unlike [`DFA`], this holds no hash maps, so it serializes the same way with any serde
+/// format and is easy to hand across an FFI boundary.
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FlatDFA<L> {
+    /// The index of the start state.
+    pub start: u32,
+    /// Whether each state is accepting, index for index.
+    pub accepting: Vec<bool>,
+    /// Every transition, as `(from, symbol, to)`.
+    pub edges: Vec<(u32, L, u32)>,
+}
+
+/// An error for text that does not describe a DFA, along with the line it was found on.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    /// The line number of the offending line, counting from 1.
+    pub line: usize,
+    /// What is wrong with the line.
+    pub kind: ParseErrorKind,
+}
+
+/// What is wrong with a line of text describing a DFA.
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    /// The line does not have the expected shape.
+    Malformed,
+    /// A state index is not a number, or does not refer to a row of the table.
+    InvalidState,
+    /// A value in a table is neither `true` nor `false`.
+    InvalidValue,
+    /// A start state is given more than once.
+    DuplicateStart,
+    /// A transition conflicts with an earlier one on the same symbol.
+    Nondeterministic,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let reason = match self.kind {
+            ParseErrorKind::Malformed => "malformed line",
+            ParseErrorKind::InvalidState => "invalid state",
+            ParseErrorKind::InvalidValue => "value must be true or false",
+            ParseErrorKind::DuplicateStart => "start state given more than once",
+            ParseErrorKind::Nondeterministic => "conflicting transition",
+        };
+        write!(f, "line {}: {}", self.line, reason)
+    }
+}
+
+/// A reference to a state in a DFA.
+///
+/// Handles are only created for indices that exist in the DFA, and the DFA cannot be modified
+/// while a handle borrows it, so lookups through a handle stay in bounds.
+pub struct State<'a, L, S, H = RandomState> {
+    dfa: &'a DFA<L, S, H>,
+    index: usize,
+}
+
+impl<'a, L, S, H> Clone for State<'a, L, S, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, L, S, H> Copy for State<'a, L, S, H> {}
+
+/// Runs a DFA one symbol at a time, remembering the state it is in.
+pub struct DFAMatcher<'a, L, S, H = RandomState> {
+    dfa: &'a DFA<L, S, H>,
+    current: Option<usize>,
+}
+
+/// A mutable reference to a state in a DFA.
+pub struct MutState<'a, L, S, H = RandomState> {
+    dfa: &'a mut DFA<L, S, H>,
+    index: usize,
+}
+
+/// A state value that records whether its state is accepting.
+///
+/// A DFA whose values implement this can be queried with [`DFA::is_accepting`], and its
+/// `_default` methods, such as [`DFA::accepts_default`], decide acceptance with it instead of
+/// taking an `is_accepting` closure.
+pub trait Accepting {
+    /// Returns true if the state holding this value is accepting.
+    fn is_accepting(&self) -> bool;
+
+    /// Marks whether the state holding this value is accepting.
+    fn set_accepting(&mut self, accepting: bool);
+}
+
+impl Accepting for bool {
+    fn is_accepting(&self) -> bool {
+        *self
+    }
+
+    fn set_accepting(&mut self, accepting: bool) {
+        *self = accepting;
+    }
+}
+
+/// The flag comes first, as in the values made by [`NFA::into_dfa`].
+impl<T> Accepting for (bool, T) {
+    fn is_accepting(&self) -> bool {
+        self.0
+    }
+
+    fn set_accepting(&mut self, accepting: bool) {
+        self.0 = accepting;
+    }
+}
+
+impl<L, S> DFA<L, S> {
+    /// Creates a new DFA with a single start state holding the given value.
+    pub fn new(start: S) -> Self {
+        Self::new_with_hasher(start)
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    H: Default,
+{
+    /// Creates a new DFA with a single start state holding the given value, whose transition
+    /// maps use the hasher `H`.
+    pub fn new_with_hasher(start: S) -> Self {
+        DFA {
+            values: vec![start],
+            transitions: vec![HashMap::default()],
+            start: 0,
+        }
+    }
+
+    /// Adds a new state with the given value and returns a handle to it.
+    pub fn add_state(&mut self, value: S) -> MutState<'_, L, S, H> {
+        let index = self.values.len();
+        self.values.push(value);
+        self.transitions.push(HashMap::default());
+        MutState::new(self, index)
+    }
+}
+
+impl<L, S, H> DFA<L, S, H> {
+    /// Pairs the value of every state with whether it satisfies `is_accepting`, so that the
+    /// result can use the `_default` methods instead of taking `is_accepting` each time.
+    pub fn with_accepting(self, is_accepting: impl Fn(&S) -> bool) -> DFA<L, (bool, S), H> {
+        self.map_values(|value| (is_accepting(&value), value))
+    }
+
+    /// Transforms the value of every state, keeping the transitions unchanged.
+    pub fn map_values<T>(self, f: impl FnMut(S) -> T) -> DFA<L, T, H> {
+        DFA {
+            values: self.values.into_iter().map(f).collect(),
+            transitions: self.transitions,
+            start: self.start,
+        }
+    }
+
+    /// Splits this DFA into the value and transitions of every state, index for index, and the
+    /// index of the start state.
+    pub fn into_parts(self) -> (Vec<S>, Vec<HashMap<L, usize, H>>, usize) {
+        (self.values, self.transitions, self.start)
+    }
+
+    /// Converts this DFA into an NFA with the same start state and transitions, whose finish
+    /// states are those whose value satisfies `is_accepting`. Transitions to states that do not
+    /// exist are dropped.
+    pub fn into_nfa(self, is_accepting: impl Fn(&S) -> bool) -> NFA<L, S> {
+        let count = self.values.len();
+        let states = self
+            .values
+            .into_iter()
+            .zip(self.transitions)
+            .map(|(value, transitions)| {
+                let mut state = crate::state::State::new(is_accepting(&value), value);
+                state.add_transitions(
+                    transitions
+                        .into_iter()
+                        .filter(|&(_, dest)| dest < count)
+                        .map(|(symbol, dest)| RealTransition::new(symbol, dest)),
+                );
+                state
+            })
+            .collect();
+
+        NFA {
+            states,
+            starts: vec![self.start],
+        }
+    }
+
+    /// Moves every symbol behind an `Rc`, so that cloning a symbol no longer clones its
+    /// contents. NFAs can do the same with `map_labels(Rc::new)`.
+    pub fn share_labels(self) -> SharedDFA<L, S, H>
+    where
+        L: Eq + Hash,
+        H: BuildHasher + Default,
+    {
+        DFA {
+            transitions: self
+                .transitions
+                .into_iter()
+                .map(|transitions| {
+                    transitions
+                        .into_iter()
+                        .map(|(symbol, dest)| (Rc::new(symbol), dest))
+                        .collect()
+                })
+                .collect(),
+            values: self.values,
+            start: self.start,
+        }
+    }
+
+    /// Transforms the symbol of every transition, keeping the states unchanged.
+    ///
+    /// Symbols of a state that are mapped to the same symbol must lead to the same state,
+    /// otherwise the DFA would become nondeterministic and an error is returned.
+    pub fn map_labels<M>(self, mut f: impl FnMut(L) -> M) -> Result<DFA<M, S, H>, DeterminismError>
+    where
+        M: Eq + Hash,
+        H: BuildHasher + Default,
+    {
+        let mut transitions = Vec::new();
+
+        for (state, state_transitions) in self.transitions.into_iter().enumerate() {
+            let mut mapped = HashMap::default();
+            for (symbol, dest) in state_transitions {
+                if mapped
+                    .insert(f(symbol), dest)
+                    .is_some_and(|prev| prev != dest)
+                {
+                    return Err(DeterminismError { state });
+                }
+            }
+            transitions.push(mapped);
+        }
+
+        Ok(DFA {
+            values: self.values,
+            transitions,
+            start: self.start,
+        })
+    }
+
+    /// Removes a state, returning its value. Transitions to it are removed, and later states
+    /// move down an index. Returns `None` if the state does not exist or is the start state.
+    pub fn remove_state(&mut self, index: usize) -> Option<S> {
+        if index >= self.values.len() || index == self.start {
+            return None;
+        }
+
+        let value = self.values.remove(index);
+        self.transitions.remove(index);
+
+        for transitions in self.transitions.iter_mut() {
+            transitions.retain(|_, dest| *dest != index);
+            for dest in transitions.values_mut() {
+                if *dest > index {
+                    *dest -= 1;
+                }
+            }
+        }
+
+        if self.start > index {
+            self.start -= 1;
+        }
+
+        Some(value)
+    }
+
+    /// Returns the number of states in this DFA.
+    pub fn state_count(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn get_state(&self, index: usize) -> Option<State<'_, L, S, H>> {
+        (index < self.values.len()).then(|| State::new(self, index))
+    }
+
+    pub fn get_state_mut(&mut self, index: usize) -> Option<MutState<'_, L, S, H>> {
+        (index < self.values.len()).then(|| MutState::new(self, index))
+    }
+
+    /// Returns whether the state at `index` is accepting, which it is not if it does not exist.
+    pub fn is_accepting(&self, index: usize) -> bool
+    where
+        S: Accepting,
+    {
+        self.values.get(index).is_some_and(S::is_accepting)
+    }
+
+    /// Marks whether the state at `index` is accepting. Returns false if there is no such
+    /// state.
+    pub fn set_accepting(&mut self, index: usize, accepting: bool) -> bool
+    where
+        S: Accepting,
+    {
+        let Some(value) = self.values.get_mut(index) else {
+            return false;
+        };
+        value.set_accepting(accepting);
+        true
+    }
+
+    /// Gets a mutable reference to the value of the state at `index`.
+    pub fn get_state_value_mut(&mut self, index: usize) -> Option<&mut S> {
+        self.values.get_mut(index)
+    }
+
+    pub fn get_start(&self) -> State<'_, L, S, H> {
+        self.get_state(self.start).unwrap()
+    }
+
+    /// Iterates over the index, value, and transitions of every state.
+    pub fn iter_states(&self) -> impl Iterator<Item = (usize, &S, &HashMap<L, usize, H>)> {
+        self.values
+            .iter()
+            .zip(self.transitions.iter())
+            .enumerate()
+            .map(|(i, (value, transitions))| (i, value, transitions))
+    }
+
+    /// Creates a matcher positioned at the start state.
+    pub fn matcher(&self) -> DFAMatcher<'_, L, S, H> {
+        DFAMatcher {
+            dfa: self,
+            current: Some(self.start),
+        }
+    }
+
+    /// Groups the states into strongly connected components, treating every transition as an
+    /// edge regardless of its symbol. Each component is sorted, and comes after every
+    /// component reachable from it.
+    pub fn sccs(&self) -> Vec<Vec<usize>> {
+        let adjacency: Vec<Vec<usize>> = self
+            .transitions
+            .iter()
+            .map(|transitions| transitions.values().copied().collect())
+            .collect();
+        graph::sccs(&adjacency)
+    }
+
+    /// Orders the states so that every transition leads from an earlier state to a later one,
+    /// which is possible exactly when the transitions form no cycle. Every state is ordered,
+    /// reachable or not.
+    pub fn topo_order(&self) -> Result<Vec<usize>, CycleError> {
+        let adjacency: Vec<Vec<usize>> = self
+            .transitions
+            .iter()
+            .map(|transitions| transitions.values().copied().collect())
+            .collect();
+        graph::topo_order(&adjacency).map_err(|state| CycleError { state })
+    }
+
+    fn get_state_transitions(&self, index: usize) -> Option<&HashMap<L, usize, H>> {
+        self.transitions.get(index)
+    }
+
+    /// Keeps only the states for which `keep` returns true, dropping transitions into removed
+    /// states. The start state must be kept. Returns the new index of every old state.
+    fn retain_states(&mut self, keep: impl Fn(usize) -> bool) -> Vec<Option<usize>> {
+        debug_assert!(keep(self.start));
+
+        let mut reassignments = Vec::with_capacity(self.values.len());
+        let mut next = 0;
+        for index in 0..self.values.len() {
+            if keep(index) {
+                reassignments.push(Some(next));
+                next += 1;
+            } else {
+                reassignments.push(None);
+            }
+        }
+
+        let values = core::mem::take(&mut self.values);
+        let transitions = core::mem::take(&mut self.transitions);
+        for ((value, mut transitions), new) in
+            values.into_iter().zip(transitions).zip(&reassignments)
+        {
+            if new.is_none() {
+                continue;
+            }
+            transitions.retain(|_, dest| match reassignments.get(*dest) {
+                Some(Some(new_dest)) => {
+                    *dest = *new_dest;
+                    true
+                }
+                Some(None) => false,
+                // dangling transitions stay dangling
+                None => true,
+            });
+            self.values.push(value);
+            self.transitions.push(transitions);
+        }
+
+        self.start = reassignments[self.start].unwrap();
+        reassignments
+    }
+}
+
+impl<L, S> DFA<L, S>
+where
+    L: Eq + Hash,
+{
+    /// Assembles a DFA from the value of every state and a list of `(from, symbol, to)`
+    /// transitions. Repeating a transition is allowed, but a state may not have transitions
+    /// to different states on the same symbol.
+    pub fn from_parts(
+        values: Vec<S>,
+        transitions: Vec<(usize, L, usize)>,
+        start: usize,
+    ) -> Result<Self, BuildError> {
+        if values.is_empty() {
+            return Err(BuildError::MissingStates);
+        }
+        if start >= values.len() {
+            return Err(BuildError::InvalidStartIndex(start));
+        }
+
+        let mut dfa = DFA {
+            transitions: (0..values.len()).map(|_| HashMap::new()).collect(),
+            values,
+            start,
+        };
+
+        for (from, symbol, to) in transitions {
+            if let Some(&index) = [from, to].iter().find(|&&i| i >= dfa.values.len()) {
+                return Err(BuildError::InvalidTransitionTarget(index));
+            }
+            if *dfa.transitions[from].entry(symbol).or_insert(to) != to {
+                return Err(DeterminismError { state: from }.into());
+            }
+        }
+
+        Ok(dfa)
+    }
+}
+
+impl<L> DFA<L, bool>
+where
+    L: Eq + Hash,
+{
+    /// Builds a chain of states that accepts exactly the given sequence of symbols. Each state
+    /// holds whether it accepts, and every other string falls off a missing transition.
+    pub fn literal<I: IntoIterator<Item = L>>(symbols: I) -> Self {
+        let mut dfa = DFA::new(false);
+        for symbol in symbols {
+            let next = dfa.values.len();
+            dfa.transitions[next - 1].insert(symbol, next);
+            dfa.add_state(false);
+        }
+        let last = dfa.values.len() - 1;
+        dfa.values[last] = true;
+        dfa
+    }
+
+    /// Builds a complete DFA with one accepting state, which accepts every string over the
+    /// given alphabet.
+    pub fn universal(alphabet: impl IntoIterator<Item = L>) -> Self {
+        Self::single_state(true, alphabet)
+    }
+
+    /// Builds a complete DFA with one state that is not accepting, which accepts no string.
+    pub fn empty_language(alphabet: impl IntoIterator<Item = L>) -> Self {
+        Self::single_state(false, alphabet)
+    }
+
+    /// Builds a DFA with one state that loops back to itself on every symbol of `alphabet`.
+    fn single_state(accepting: bool, alphabet: impl IntoIterator<Item = L>) -> Self {
+        let mut dfa = DFA::new(accepting);
+        dfa.transitions[0].extend(alphabet.into_iter().map(|symbol| (symbol, 0)));
+        dfa
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<L> DFA<L, bool>
+where
+    L: Eq + Hash,
+{
+    /// Rebuilds a DFA from its flattened form, with each state holding whether it accepts.
+    pub fn from_flat(flat: FlatDFA<L>) -> Result<Self, BuildError> {
+        let edges = flat
+            .edges
+            .into_iter()
+            .map(|(from, symbol, to)| (from as usize, symbol, to as usize))
+            .collect();
+        DFA::from_parts(flat.accepting, edges, flat.start as usize)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Clone,
+{
+    /// Flattens this DFA into plain data, marking the states that satisfy `is_accepting`.
+    /// Transitions to states that do not exist are left out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` states.
+    pub fn to_flat(&self, is_accepting: impl Fn(&S) -> bool) -> FlatDFA<L> {
+        let index = |i: usize| u32::try_from(i).expect("state index must fit in a u32");
+
+        let mut edges = Vec::new();
+        for (from, transitions) in self.transitions.iter().enumerate() {
+            for (symbol, &to) in transitions.iter() {
+                if to < self.values.len() {
+                    edges.push((index(from), symbol.clone(), index(to)));
+                }
+            }
+        }
+
+        FlatDFA {
+            start: index(self.start),
+            accepting: self.values.iter().map(is_accepting).collect(),
+            edges,
+        }
+    }
+}
+
+impl DFA<char, Option<String>> {
+    /// Builds a trie that accepts exactly the given keywords. Keywords with a common prefix
+    /// share the states for it, and each accepting state holds the keyword it matched.
+    pub fn from_keywords<I>(keywords: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let mut dfa = DFA::new(None);
+
+        for keyword in keywords {
+            let keyword = keyword.into();
+            let mut state = dfa.start;
+            for c in keyword.chars() {
+                state = match dfa.transitions[state].get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        let next = dfa.add_state(None).index();
+                        dfa.transitions[state].insert(c, next);
+                        next
+                    }
+                };
+            }
+            dfa.values[state] = Some(keyword);
+        }
+
+        dfa
+    }
+}
+
+impl DFA<char, bool> {
+    /// Parses a DFA whose state values say whether each state is accepting.
+    ///
+    /// Each line is either `start: 0`, `accept: 1 2`, or a transition like `0 -a-> 1`, and
+    /// states are numbered from 0 without gaps. The start state is 0 unless given. The table
+    /// written by `Display` is also accepted, which is recognized by its first line starting
+    /// with `|`. Blank lines and lines starting with `#` are ignored.
+    pub fn from_str_table(input: &str) -> Result<Self, ParseError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(i, text)| (i + 1, text.trim()))
+            .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+            .peekable();
+
+        match lines.peek() {
+            // the header of a table starts with the two empty cells above the state indices
+            // and values, while an edge list always starts with a state index
+            Some((_, text)) if text.starts_with('|') => Self::parse_table(lines),
+            _ => Self::parse_edges(lines),
+        }
+    }
+
+    fn parse_edges<'a>(lines: impl Iterator<Item = (usize, &'a str)>) -> Result<Self, ParseError> {
+        let index = |line, text: &str| {
+            text.parse::<usize>().map_err(|_| ParseError {
+                line,
+                kind: ParseErrorKind::InvalidState,
+            })
+        };
+
+        let mut start = None;
+        let mut accepting = Vec::new();
+        let mut edges = Vec::new();
+
+        for (line, text) in lines {
+            if let Some(rest) = text.strip_prefix("start:") {
+                if start.is_some() {
+                    return Err(ParseError {
+                        line,
+                        kind: ParseErrorKind::DuplicateStart,
+                    });
+                }
+                start = Some((line, index(line, rest.trim())?));
+            } else if let Some(rest) = text.strip_prefix("accept:") {
+                for token in rest.split(|c: char| c == ',' || c.is_whitespace()) {
+                    if !token.is_empty() {
+                        accepting.push((line, index(line, token)?));
+                    }
+                }
+            } else {
+                let malformed = || ParseError {
+                    line,
+                    kind: ParseErrorKind::Malformed,
+                };
+                let tokens: Vec<&str> = text.split_whitespace().collect();
+                let [from, arrow, to] = tokens[..] else {
+                    return Err(malformed());
+                };
+                let mut symbol = arrow
+                    .strip_prefix('-')
+                    .and_then(|arrow| arrow.strip_suffix("->"))
+                    .ok_or_else(malformed)?
+                    .chars();
+                let (Some(symbol), None) = (symbol.next(), symbol.next()) else {
+                    return Err(malformed());
+                };
+                edges.push((line, index(line, from)?, symbol, index(line, to)?));
+            }
+        }
+
+        // states are numbered from 0 without gaps, so every one must be below the number of
+        // distinct states mentioned
+        let (start_line, start) = start.unwrap_or((0, 0));
+        let mentions: Vec<(usize, usize)> = edges
+            .iter()
+            .flat_map(|&(line, from, _, to)| [(line, from), (line, to)])
+            .chain(accepting.iter().copied())
+            .chain([(start_line, start)])
+            .collect();
+        let count = mentions
+            .iter()
+            .map(|&(_, state)| state)
+            .collect::<HashSet<usize>>()
+            .len();
+        if let Some(&(line, _)) = mentions.iter().find(|&&(_, state)| state >= count) {
+            return Err(ParseError {
+                line,
+                kind: ParseErrorKind::InvalidState,
+            });
+        }
+
+        let mut dfa = DFA {
+            values: vec![false; count],
+            transitions: (0..count).map(|_| HashMap::new()).collect(),
+            start,
+        };
+        for (_, state) in accepting {
+            dfa.values[state] = true;
+        }
+        for (line, from, symbol, to) in edges {
+            if *dfa.transitions[from].entry(symbol).or_insert(to) != to {
+                return Err(ParseError {
+                    line,
+                    kind: ParseErrorKind::Nondeterministic,
+                });
+            }
+        }
+
+        Ok(dfa)
+    }
+
+    fn parse_table<'a>(
+        mut lines: impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<Self, ParseError> {
+        let (header_line, header) = lines.next().unwrap();
+        let malformed = |line| ParseError {
+            line,
+            kind: ParseErrorKind::Malformed,
+        };
+
+        let mut symbols = Vec::new();
+        for cell in header.split('|').skip(2) {
+            let mut chars = cell.trim().chars();
+            let (Some(symbol), None) = (chars.next(), chars.next()) else {
+                return Err(malformed(header_line));
+            };
+            symbols.push(symbol);
+        }
+
+        let mut start = None;
+        let mut values = Vec::new();
+        let mut rows = Vec::new();
+
+        for (line, text) in lines {
+            let mut cells = text.split('|').map(str::trim);
+            let (Some(index), Some(value)) = (cells.next(), cells.next()) else {
+                return Err(malformed(line));
+            };
+
+            let (is_start, index) = match index.strip_prefix('>') {
+                Some(index) => (true, index.trim()),
+                None => (false, index),
+            };
+            if index.parse() != Ok(values.len()) {
+                return Err(ParseError {
+                    line,
+                    kind: ParseErrorKind::InvalidState,
+                });
+            }
+            if is_start && start.replace(values.len()).is_some() {
+                return Err(ParseError {
+                    line,
+                    kind: ParseErrorKind::DuplicateStart,
+                });
+            }
+
+            values.push(match value {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    return Err(ParseError {
+                        line,
+                        kind: ParseErrorKind::InvalidValue,
+                    })
+                }
+            });
+
+            let dests: Vec<&str> = cells.collect();
+            if dests.len() > symbols.len() {
+                return Err(malformed(line));
+            }
+            rows.push((line, dests));
+        }
+
+        if values.is_empty() {
+            return Err(malformed(header_line));
+        }
+
+        let mut transitions = Vec::with_capacity(rows.len());
+        for (line, dests) in rows {
+            let mut state_transitions = HashMap::new();
+            for (&symbol, dest) in symbols.iter().zip(dests) {
+                if dest.is_empty() {
+                    continue;
+                }
+                match dest.parse::<usize>() {
+                    Ok(dest) if dest < values.len() => {
+                        if *state_transitions.entry(symbol).or_insert(dest) != dest {
+                            return Err(ParseError {
+                                line,
+                                kind: ParseErrorKind::Nondeterministic,
+                            });
+                        }
+                    }
+                    _ => {
+                        return Err(ParseError {
+                            line,
+                            kind: ParseErrorKind::InvalidState,
+                        })
+                    }
+                }
+            }
+            transitions.push(state_transitions);
+        }
+
+        Ok(DFA {
+            values,
+            transitions,
+            start: start.unwrap_or(0),
+        })
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Returns every symbol used by a transition of this DFA.
+    pub fn alphabet(&self) -> HashSet<&L> {
+        self.transitions.iter().flat_map(|trs| trs.keys()).collect()
+    }
+
+    /// Counts the states and transitions of this DFA, and the states whose value satisfies
+    /// `is_accepting`.
+    pub fn stats(&self, is_accepting: impl Fn(&S) -> bool) -> AutomatonStats {
+        let alphabet_len = self.alphabet().len();
+        AutomatonStats {
+            num_states: self.values.len(),
+            num_transitions: self.transitions.iter().map(HashMap::len).sum(),
+            num_epsilon_transitions: 0,
+            num_accepting: self.values.iter().filter(|&v| is_accepting(v)).count(),
+            is_complete: self.transitions.iter().all(|trs| {
+                trs.values()
+                    .filter(|&&dest| dest < self.values.len())
+                    .count()
+                    == alphabet_len
+            }),
+            max_out_degree: self.transitions.iter().map(HashMap::len).max().unwrap_or(0),
+        }
+    }
+
+    /// Follows the transitions for each input from the start state.
+    /// Returns `None` if some input has no transition.
+    pub fn traverse<I>(&self, inputs: I) -> Option<State<'_, L, S, H>>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        self.traverse_detailed(inputs).ok()
+    }
+
+    /// Follows the transitions for each input from the start state.
+    /// Returns the position of the first input that has no transition on failure.
+    pub fn traverse_detailed<I>(&self, inputs: I) -> Result<State<'_, L, S, H>, usize>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        let mut curr = self.get_start();
+        for (i, input) in inputs.enumerate() {
+            curr = curr.next(input.borrow()).ok_or(i)?;
+        }
+        Ok(curr)
+    }
+
+    /// Traverses this DFA like [`DFA::traverse`], also returning the index of every state
+    /// visited on the way, starting with the start state.
+    pub fn traverse_with_path<I>(&self, inputs: I) -> Option<(State<'_, L, S, H>, Vec<usize>)>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        let mut curr = self.get_start();
+        let mut path = vec![curr.index()];
+        for input in inputs {
+            curr = curr.next(input.borrow())?;
+            path.push(curr.index());
+        }
+        Some((curr, path))
+    }
+
+    /// Returns whether the state reached by the inputs satisfies `is_accepting`.
+    /// Returns `false` if some input has no transition.
+    pub fn accepts<I>(&self, inputs: I, is_accepting: impl Fn(&S) -> bool) -> bool
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        self.traverse(inputs)
+            .is_some_and(|state| is_accepting(state.value()))
+    }
+
+    /// Finds the longest prefix of the inputs that leads to a state satisfying `is_accepting`.
+    /// Returns the number of inputs in that prefix and the state it leads to, or `None` if no
+    /// prefix, including the empty one, is accepted.
+    pub fn longest_match<I>(
+        &self,
+        inputs: I,
+        is_accepting: impl Fn(&S) -> bool,
+    ) -> Option<(usize, State<'_, L, S, H>)>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        let mut curr = self.get_start();
+        let mut longest = is_accepting(curr.value()).then_some((0, curr));
+
+        for (i, input) in inputs.enumerate() {
+            let Some(next) = curr.next(input.borrow()) else {
+                break;
+            };
+            curr = next;
+            if is_accepting(curr.value()) {
+                longest = Some((i + 1, curr));
+            }
+        }
+
+        longest
+    }
+
+    /// Finds the shortest prefix of the inputs that leads to a state satisfying `is_accepting`,
+    /// stopping as soon as one is found. Returns the number of inputs in that prefix, which is
+    /// 0 if the start state is accepting, or `None` if no prefix is accepted.
+    pub fn matches_prefix<I>(&self, inputs: I, is_accepting: impl Fn(&S) -> bool) -> Option<usize>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        let mut curr = self.get_start();
+        if is_accepting(curr.value()) {
+            return Some(0);
+        }
+
+        for (i, input) in inputs.enumerate() {
+            curr = curr.next(input.borrow())?;
+            if is_accepting(curr.value()) {
+                return Some(i + 1);
+            }
+        }
+
+        None
+    }
+}
+
+impl<S, H> DFA<u8, S, H>
+where
+    H: BuildHasher,
+{
+    /// Follows the transitions for each byte from the start state.
+    /// Returns `None` if some byte has no transition.
+    pub fn traverse_bytes(&self, bytes: &[u8]) -> Option<State<'_, u8, S, H>> {
+        self.traverse(bytes.iter())
+    }
+}
+
+impl<S, H> DFA<char, S, H>
+where
+    H: BuildHasher,
+{
+    /// Follows the transitions for each character of `input` from the start state.
+    /// Returns `None` if some character has no transition.
+    pub fn traverse_str(&self, input: &str) -> Option<State<'_, char, S, H>> {
+        self.traverse(input.chars())
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Returns the indices of the states reachable from the start state, in breadth-first order.
+    fn reachable_order(&self) -> Vec<usize> {
+        let mut order = vec![self.start];
+        let mut seen = HashSet::from([self.start]);
+        let mut i = 0;
+        while i < order.len() {
+            for &dest in self.transitions[order[i]].values() {
+                if dest < self.values.len() && seen.insert(dest) {
+                    order.push(dest);
+                }
+            }
+            i += 1;
+        }
+        order
+    }
+
+    /// Returns the indices of the states reachable from the start state.
+    pub fn reachable_states(&self) -> HashSet<usize> {
+        self.reachable_order().into_iter().collect()
+    }
+
+    /// Removes every state that is not reachable from the start state. Returns the new index
+    /// of every old state, or `None` for the states that were removed.
+    pub fn remove_unreachable_states(&mut self) -> Vec<Option<usize>> {
+        let reachable = self.reachable_states();
+        self.retain_states(|index| reachable.contains(&index))
+    }
+
+    /// Returns true if no state satisfying `is_accepting` is reachable from the start,
+    /// meaning this DFA accepts no strings.
+    pub fn is_empty(&self, is_accepting: impl Fn(&S) -> bool) -> bool {
+        !self
+            .reachable_order()
+            .into_iter()
+            .any(|state| is_accepting(&self.values[state]))
+    }
+
+    /// Returns the states that are reachable from the start and can reach a state
+    /// satisfying `is_accepting`.
+    fn useful_states(&self, is_accepting: impl Fn(&S) -> bool) -> HashSet<usize> {
+        let reachable = self.reachable_states();
+        let coreachable = self.coreachable_states(is_accepting);
+        reachable.intersection(&coreachable).copied().collect()
+    }
+
+    /// Returns the states from which a state satisfying `is_accepting` can be reached.
+    fn coreachable_states(&self, is_accepting: impl Fn(&S) -> bool) -> HashSet<usize> {
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.values.len()];
+        for (state, transitions) in self.transitions.iter().enumerate() {
+            for &dest in transitions.values() {
+                if let Some(predecessors) = predecessors.get_mut(dest) {
+                    predecessors.push(state);
+                }
+            }
+        }
+
+        let mut stack: Vec<usize> = (0..self.values.len())
+            .filter(|&state| is_accepting(&self.values[state]))
+            .collect();
+        let mut coreachable: HashSet<usize> = stack.iter().copied().collect();
+        while let Some(state) = stack.pop() {
+            for &prev in predecessors[state].iter() {
+                if coreachable.insert(prev) {
+                    stack.push(prev);
+                }
+            }
+        }
+
+        coreachable
+    }
+
+    /// Removes every state from which no state satisfying `is_accepting` can be reached. The
+    /// start state is always kept, so a DFA accepting nothing is left with a single state.
+    pub fn remove_dead_states(&mut self, is_accepting: impl Fn(&S) -> bool) {
+        let coreachable = self.coreachable_states(is_accepting);
+        let start = self.start;
+        self.retain_states(|index| index == start || coreachable.contains(&index));
+    }
+
+    /// Returns true if this DFA accepts finitely many strings, which is the case unless some
+    /// cycle lies on a path from the start to a state satisfying `is_accepting`.
+    pub fn is_finite(&self, is_accepting: impl Fn(&S) -> bool) -> bool {
+        let useful = self.useful_states(is_accepting);
+
+        // depth-first search for a back edge, only walking useful states
+        let mut finished: HashSet<usize> = HashSet::new();
+        let mut on_path: HashSet<usize> = HashSet::new();
+
+        for &root in useful.iter() {
+            if finished.contains(&root) {
+                continue;
+            }
+
+            let mut stack = vec![(root, self.transitions[root].values())];
+            on_path.insert(root);
+
+            while let Some((state, dests)) = stack.last_mut() {
+                let state = *state;
+                match dests.find(|&dest| useful.contains(dest)) {
+                    Some(&dest) if on_path.contains(&dest) => return false,
+                    Some(&dest) if !finished.contains(&dest) => {
+                        on_path.insert(dest);
+                        stack.push((dest, self.transitions[dest].values()));
+                    }
+                    Some(_) => {}
+                    None => {
+                        on_path.remove(&state);
+                        finished.insert(state);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Counts the strings of exactly `length` symbols that lead to a state satisfying
+    /// `is_accepting`. The count saturates at `u128::MAX`.
+    ///
+    /// Only states on a path from the start to an accepting state matter. If they form no
+    /// cycle, the count follows their topological order and `length` is at most their
+    /// number. Otherwise it steps one row of counts per symbol, or for lengths beyond the
+    /// square of their number, raises the matrix of transition counts to the power `length`
+    /// by squaring.
+    pub fn count_accepted(&self, length: usize, is_accepting: impl Fn(&S) -> bool) -> u128 {
+        let useful = self.useful_states(&is_accepting);
+        if !useful.contains(&self.start) {
+            return 0;
+        }
+
+        // renumber the useful states from 0, in order, keeping only edges between them
+        let mut states: Vec<usize> = useful.iter().copied().collect();
+        states.sort_unstable();
+        let mut compact = vec![usize::MAX; self.values.len()];
+        for (i, &state) in states.iter().enumerate() {
+            compact[state] = i;
+        }
+        let adjacency: Vec<Vec<usize>> = states
+            .iter()
+            .map(|&state| {
+                self.transitions[state]
+                    .values()
+                    .filter(|&&dest| useful.contains(&dest))
+                    .map(|&dest| compact[dest])
+                    .collect()
+            })
+            .collect();
+        let accepting: Vec<u128> = states
+            .iter()
+            .map(|&state| u128::from(is_accepting(&self.values[state])))
+            .collect();
+        let start = compact[self.start];
+        let n = states.len();
+
+        if let Ok(order) = graph::topo_order(&adjacency) {
+            // without a cycle, no path visits a state twice, so none has as many symbols as states
+            if length >= n {
+                return 0;
+            }
+
+            // paths[state][k] counts the strings of k symbols leading from the start to state
+            let mut paths = vec![vec![0u128; length + 1]; n];
+            paths[start][0] = 1;
+            for state in order {
+                for &dest in adjacency[state].iter() {
+                    for k in 0..length {
+                        paths[dest][k + 1] = paths[dest][k + 1].saturating_add(paths[state][k]);
+                    }
+                }
+            }
+            return (0..n)
+                .filter(|&state| accepting[state] == 1)
+                .fold(0, |sum, state| sum.saturating_add(paths[state][length]));
+        }
+
+        if length > n.saturating_mul(n) {
+            return Self::count_by_squaring(&adjacency, &accepting, length, start);
+        }
+
+        // row[state] counts the strings of k symbols leading from state to an accepting one
+        let mut row = accepting;
+        for _ in 0..length {
+            let next: Vec<u128> = adjacency
+                .iter()
+                .map(|dests| {
+                    dests
+                        .iter()
+                        .fold(0u128, |sum, &dest| sum.saturating_add(row[dest]))
+                })
+                .collect();
+            // every later row would be the same
+            if next == row {
+                break;
+            }
+            row = next;
+        }
+        row[start]
+    }
+
+    /// Counts the strings of `length` symbols leading from `start` to an accepting state by
+    /// multiplying `accepting` by the `length`th power of the matrix of transition counts.
+    /// Saturating arithmetic never undercounts, since every count is nonnegative.
+    fn count_by_squaring(
+        adjacency: &[Vec<usize>],
+        accepting: &[u128],
+        length: usize,
+        start: usize,
+    ) -> u128 {
+        let n = adjacency.len();
+        let mut power = vec![vec![0u128; n]; n];
+        for (state, dests) in adjacency.iter().enumerate() {
+            for &dest in dests {
+                power[state][dest] += 1;
+            }
+        }
+
+        let mut counts = accepting.to_vec();
+        let mut remaining = length;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                counts = power
+                    .iter()
+                    .map(|row| {
+                        row.iter().zip(&counts).fold(0u128, |sum, (&a, &b)| {
+                            sum.saturating_add(a.saturating_mul(b))
+                        })
+                    })
+                    .collect();
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                power = (0..n)
+                    .map(|i| {
+                        (0..n)
+                            .map(|j| {
+                                (0..n).fold(0u128, |sum, k| {
+                                    sum.saturating_add(power[i][k].saturating_mul(power[k][j]))
+                                })
+                            })
+                            .collect()
+                    })
+                    .collect();
+            }
+        }
+        counts[start]
+    }
+
+    /// Returns, for every `k` up to `length` and every state, how many strings of `k` symbols
+    /// lead from that state to one satisfying `is_accepting`.
+    #[cfg(feature = "rand")]
+    fn accepted_counts(&self, length: usize, is_accepting: impl Fn(&S) -> bool) -> Vec<Vec<u128>> {
+        let mut counts = Vec::with_capacity(length + 1);
+        counts.push(
+            self.values
+                .iter()
+                .map(|value| u128::from(is_accepting(value)))
+                .collect::<Vec<_>>(),
+        );
+
+        for k in 1..=length {
+            let previous = &counts[k - 1];
+            let next = self
+                .transitions
+                .iter()
+                .map(|transitions| {
+                    transitions
+                        .values()
+                        .filter_map(|&dest| previous.get(dest))
+                        .fold(0u128, |sum, &count| sum.saturating_add(count))
+                })
+                .collect();
+            counts.push(next);
+        }
+
+        counts
+    }
+
+    /// Returns whether `other` has the same structure as this DFA up to the numbering of its
+    /// states, ignoring their values. Only states reachable from the start are compared.
+    pub fn is_isomorphic<S2, H2>(&self, other: &DFA<L, S2, H2>) -> bool
+    where
+        H2: BuildHasher,
+    {
+        self.is_isomorphic_by(other, |_, _| true)
+    }
+
+    /// Returns whether `other` has the same structure as this DFA up to the numbering of its
+    /// states, where matched states must also satisfy `values_eq`. Only states reachable from
+    /// the start are compared.
+    pub fn is_isomorphic_by<S2, H2>(
+        &self,
+        other: &DFA<L, S2, H2>,
+        values_eq: impl Fn(&S, &S2) -> bool,
+    ) -> bool
+    where
+        H2: BuildHasher,
+    {
+        // the state of `other` matched with each state of this DFA, and the reverse
+        let mut forward = vec![None; self.values.len()];
+        let mut backward = vec![None; other.values.len()];
+        forward[self.start] = Some(other.start);
+        backward[other.start] = Some(self.start);
+        let mut queue = VecDeque::from([(self.start, other.start)]);
+
+        while let Some((a, b)) = queue.pop_front() {
+            if !values_eq(&self.values[a], &other.values[b])
+                || self.transitions[a].len() != other.transitions[b].len()
+            {
+                return false;
+            }
+
+            for (symbol, &a_dest) in self.transitions[a].iter() {
+                let Some(&b_dest) = other.transitions[b].get(symbol) else {
+                    return false;
+                };
+                match (forward.get(a_dest), backward.get(b_dest)) {
+                    // both dangling
+                    (None, None) => {}
+                    (Some(None), Some(None)) => {
+                        forward[a_dest] = Some(b_dest);
+                        backward[b_dest] = Some(a_dest);
+                        queue.push_back((a_dest, b_dest));
+                    }
+                    (Some(&matched), _) if matched == Some(b_dest) => {}
+                    _ => return false,
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Builds the minimal DFA for the same language by reversing and determinizing twice, as
+    /// a slower but simpler alternative to [`DFA::minimize`]. Each state holds whether it is
+    /// accepting, and states from which nothing is accepted are left out.
+    pub fn minimize_brzozowski(self, is_accepting: impl Fn(&S) -> bool) -> DFA<L, bool>
+    where
+        L: Clone,
+    {
+        let dfa = self.map_values(|v| is_accepting(&v));
+        reverse_determinize(&reverse_determinize(&dfa))
+    }
+
+    /// Merges equivalent states using Hopcroft's partition refinement algorithm.
+    ///
+    /// `is_accepting` decides which states are accepting, and `merge` combines the values of
+    /// states that are coalesced into one. States that are unreachable, or from which no
+    /// accepting state can be reached, are removed.
+    pub fn minimize<A, M>(mut self, is_accepting: A, mut merge: M) -> DFA<L, S, H>
+    where
+        A: Fn(&S) -> bool,
+        M: FnMut(Vec<S>) -> S,
+        H: Default,
+    {
+        // only reachable states take part, renumbered compactly; `sink` stands in for every
+        // missing or dangling transition
+        let order = self.reachable_order();
+        let compact: HashMap<usize, usize> =
+            order.iter().enumerate().map(|(i, &s)| (s, i)).collect();
+        let sink = order.len();
+
+        let mut alphabet: HashMap<&L, usize> = HashMap::new();
+        for &s in order.iter() {
+            for symbol in self.transitions[s].keys() {
+                let len = alphabet.len();
+                alphabet.entry(symbol).or_insert(len);
+            }
+        }
+
+        // predecessors[c][q] are the states that move to `q` on symbol `c`
+        let mut predecessors = vec![vec![Vec::new(); sink + 1]; alphabet.len()];
+        for (p, &s) in order.iter().enumerate() {
+            let mut missing: HashSet<usize> = (0..alphabet.len()).collect();
+            for (symbol, dest) in self.transitions[s].iter() {
+                let c = alphabet[symbol];
+                missing.remove(&c);
+                predecessors[c][compact.get(dest).copied().unwrap_or(sink)].push(p);
+            }
+            for c in missing {
+                predecessors[c][sink].push(p);
+            }
+        }
+        for symbol_predecessors in predecessors.iter_mut() {
+            symbol_predecessors[sink].push(sink);
+        }
+
+        let (accepting, rejecting): (Vec<usize>, Vec<usize>) =
+            (0..=sink).partition(|&q| q != sink && is_accepting(&self.values[order[q]]));
+
+        let mut blocks: Vec<Vec<usize>> = Vec::new();
+        let mut block_of = vec![0; sink + 1];
+        for block in [rejecting, accepting] {
+            if !block.is_empty() {
+                for &q in block.iter() {
+                    block_of[q] = blocks.len();
+                }
+                blocks.push(block);
+            }
+        }
+
+        let mut waiting: HashSet<(usize, usize)> = HashSet::new();
+        let mut worklist = Vec::new();
+        let smallest = (0..blocks.len()).min_by_key(|&b| blocks[b].len()).unwrap();
+        for c in 0..alphabet.len() {
+            waiting.insert((smallest, c));
+            worklist.push((smallest, c));
+        }
+
+        while let Some((splitter, c)) = worklist.pop() {
+            waiting.remove(&(splitter, c));
+
+            let movers: HashSet<usize> = blocks[splitter]
+                .iter()
+                .flat_map(|&q| predecessors[c][q].iter().copied())
+                .collect();
+
+            let mut touched: HashMap<usize, Vec<usize>> = HashMap::new();
+            for &p in movers.iter() {
+                touched.entry(block_of[p]).or_default().push(p);
+            }
+
+            for (block, inside) in touched {
+                if inside.len() == blocks[block].len() {
+                    continue;
+                }
+
+                let split = blocks.len();
+                blocks[block].retain(|q| !movers.contains(q));
+                for &q in inside.iter() {
+                    block_of[q] = split;
+                }
+                blocks.push(inside);
+
+                for c in 0..alphabet.len() {
+                    let next = if waiting.contains(&(block, c))
+                        || blocks[split].len() <= blocks[block].len()
+                    {
+                        split
+                    } else {
+                        block
+                    };
+                    if waiting.insert((next, c)) {
+                        worklist.push((next, c));
+                    }
+                }
+            }
+        }
+
+        // the alphabet borrows the symbols, which are moved out below
+        drop(alphabet);
+
+        // number the surviving blocks in the order their first member was reached
+        let dead = block_of[sink];
+        let mut renumber: HashMap<usize, usize> = HashMap::new();
+        for (q, &block) in block_of[..sink].iter().enumerate() {
+            if block != dead || q == 0 {
+                let len = renumber.len();
+                renumber.entry(block).or_insert(len);
+            }
+        }
+
+        let mut values: Vec<Option<S>> = core::mem::take(&mut self.values)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut merged: Vec<Vec<S>> = (0..renumber.len()).map(|_| Vec::new()).collect();
+        let mut transitions: Vec<HashMap<L, usize, H>> =
+            (0..renumber.len()).map(|_| HashMap::default()).collect();
+        let mut filled = vec![false; renumber.len()];
+
+        for (q, &s) in order.iter().enumerate() {
+            let Some(&index) = renumber.get(&block_of[q]) else {
+                continue;
+            };
+
+            merged[index].push(values[s].take().unwrap());
+
+            if !filled[index] {
+                filled[index] = true;
+                for (symbol, dest) in core::mem::take(&mut self.transitions[s]) {
+                    let block = block_of[compact.get(&dest).copied().unwrap_or(sink)];
+                    if block != dead {
+                        transitions[index].insert(symbol, renumber[&block]);
+                    }
+                }
+            }
+        }
+
+        DFA {
+            values: merged.into_iter().map(&mut merge).collect(),
+            transitions,
+            start: 0,
+        }
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Clone + Eq + Hash,
+    H: BuildHasher + Default,
+{
+    /// Finds a shortest string leading from the start to a state satisfying `is_accepting`.
+    /// Returns `None` if no accepting state is reachable.
+    pub fn shortest_accepted(&self, is_accepting: impl Fn(&S) -> bool) -> Option<Vec<L>> {
+        self.shortest_path_where(self.start, |state| is_accepting(&self.values[state]))
+    }
+
+    /// Finds a shortest string leading from state `from` to state `to`, which is empty if they
+    /// are the same state. Returns `None` if `to` cannot be reached from `from`.
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<L>> {
+        self.shortest_path_where(from, |state| state == to)
+    }
+
+    /// Splits an accepted string into `x`, `y` and `z`, where `y` is nonempty and every
+    /// `x y^n z` is accepted too. Returns `None` if the language is finite.
+    ///
+    /// `y` goes around a shortest cycle through the first such state found breadth first from the
+    /// start, and `x` and `z` are shortest paths to and from that state.
+    pub fn pumping_witness(
+        &self,
+        is_accepting: impl Fn(&S) -> bool,
+    ) -> Option<(Vec<L>, Vec<L>, Vec<L>)> {
+        let useful = self.useful_states(&is_accepting);
+
+        let mut component = vec![usize::MAX; self.values.len()];
+        for (i, members) in self.sccs().into_iter().enumerate() {
+            for state in members {
+                component[state] = i;
+            }
+        }
+
+        for state in self.reachable_order() {
+            if !useful.contains(&state) {
+                continue;
+            }
+
+            // a shortest trip around a cycle starts with some edge that stays in the component
+            let y = self.transitions[state]
+                .iter()
+                .filter(|&(_, &dest)| {
+                    dest < self.values.len() && component[dest] == component[state]
+                })
+                .filter_map(|(symbol, &dest)| {
+                    let mut cycle = vec![symbol.clone()];
+                    cycle.extend(self.shortest_path(dest, state)?);
+                    Some(cycle)
+                })
+                .min_by_key(Vec::len);
+
+            if let Some(y) = y {
+                let x = self.shortest_path(self.start, state)?;
+                let z = self.shortest_path_where(state, |s| is_accepting(&self.values[s]))?;
+                return Some((x, y, z));
+            }
+        }
+
+        None
+    }
+
+    /// Draws a string of exactly `length` symbols uniformly at random from those leading to a
+    /// state satisfying `is_accepting`. Returns `None` if there are none.
+    ///
+    /// Each symbol is chosen with probability proportional to the number of accepted strings
+    /// that continue through it, as counted by [`DFA::count_accepted`]. The draw is only
+    /// uniform while those counts do not saturate.
+    #[cfg(feature = "rand")]
+    pub fn sample_accepted<R>(
+        &self,
+        length: usize,
+        is_accepting: impl Fn(&S) -> bool,
+        rng: &mut R,
+    ) -> Option<Vec<L>>
+    where
+        R: rand::Rng + ?Sized,
+    {
+        let counts = self.accepted_counts(length, is_accepting);
+        let mut curr = self.start;
+        let mut sampled = Vec::with_capacity(length);
+
+        for remaining in (1..=length).rev() {
+            let total = counts[remaining][curr];
+            if total == 0 {
+                return None;
+            }
+
+            let mut pick = rng.gen_range(0..total);
+            for (symbol, &dest) in self.transitions[curr].iter() {
+                let Some(&count) = counts[remaining - 1].get(dest) else {
+                    continue;
+                };
+                if pick < count {
+                    sampled.push(symbol.clone());
+                    curr = dest;
+                    break;
+                }
+                pick -= count;
+            }
+        }
+
+        (counts[0][curr] > 0).then_some(sampled)
+    }
+
+    /// Yields every string of at most `max_len` symbols that leads to a state satisfying
+    /// `is_accepting`, shortest first.
+    ///
+    /// Only states that can still reach an accepting state are explored, so this terminates
+    /// on DFAs with cycles and never walks into dead ends.
+    pub fn accepted_up_to<'a, A>(
+        &'a self,
+        max_len: usize,
+        is_accepting: A,
+    ) -> impl Iterator<Item = Vec<L>> + 'a
+    where
+        A: Fn(&S) -> bool + 'a,
+    {
+        let useful = self.useful_states(&is_accepting);
+        let mut queue = VecDeque::new();
+        if useful.contains(&self.start) {
+            queue.push_back((self.start, Vec::new()));
+        }
+
+        core::iter::from_fn(move || {
+            while let Some((state, prefix)) = queue.pop_front() {
+                if prefix.len() < max_len {
+                    for (symbol, &dest) in self.transitions[state].iter() {
+                        if useful.contains(&dest) {
+                            let mut next = prefix.clone();
+                            next.push(symbol.clone());
+                            queue.push_back((dest, next));
+                        }
+                    }
+                }
+
+                if is_accepting(&self.values[state]) {
+                    return Some(prefix);
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Searches breadth-first from `from` for a state satisfying `is_target`,
+    /// returning the symbols along the path to the first one found.
+    fn shortest_path_where(
+        &self,
+        from: usize,
+        is_target: impl Fn(usize) -> bool,
+    ) -> Option<Vec<L>> {
+        if from >= self.values.len() {
+            return None;
+        }
+
+        let mut predecessors: HashMap<usize, (usize, &L)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut seen = HashSet::from([from]);
+
+        while let Some(state) = queue.pop_front() {
+            if is_target(state) {
+                let mut path = Vec::new();
+                let mut curr = state;
+                while let Some(&(prev, symbol)) = predecessors.get(&curr) {
+                    path.push(symbol.clone());
+                    curr = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (symbol, &dest) in self.transitions[state].iter() {
+                if dest < self.values.len() && seen.insert(dest) {
+                    predecessors.insert(dest, (state, symbol));
+                    queue.push_back(dest);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Builds the product of this DFA and `other`, which accepts exactly the strings both accept.
+    /// Each state holds the values of the pair of states it is made of.
+    pub fn intersect<S2>(&self, other: &DFA<L, S2, H>) -> DFA<L, (S, S2), H>
+    where
+        S: Clone,
+        S2: Clone,
+    {
+        self.product(other, false, |a, b| {
+            (a.unwrap().clone(), b.unwrap().clone())
+        })
+    }
+
+    /// Builds the product of this DFA and `other`, which accepts exactly the strings either accepts.
+    /// Each state holds the values of the pair of states it is made of, where `None` stands for
+    /// the implicit trap state a DFA moves to when it has no transition.
+    pub fn union<S2>(&self, other: &DFA<L, S2, H>) -> DFA<L, (Option<S>, Option<S2>), H>
+    where
+        S: Clone,
+        S2: Clone,
+    {
+        self.product(other, true, |a, b| (a.cloned(), b.cloned()))
+    }
+
+    /// Builds the product of this DFA and `other`, which accepts exactly the strings this one
+    /// accepts and `other` rejects. Each state holds whether it is accepting.
+    pub fn difference<S2>(
+        &self,
+        other: &DFA<L, S2, H>,
+        is_accepting: impl Fn(&S) -> bool,
+        other_accepting: impl Fn(&S2) -> bool,
+    ) -> DFA<L, bool, H> {
+        self.product(other, true, |a, b| {
+            a.is_some_and(&is_accepting) && !b.is_some_and(&other_accepting)
+        })
+    }
+
+    /// Checks that every string this DFA accepts is also accepted by `other`. Otherwise, returns
+    /// a shortest string that this DFA accepts and `other` rejects.
+    pub fn is_subset_of<S2>(
+        &self,
+        other: &DFA<L, S2, H>,
+        is_accepting: impl Fn(&S) -> bool,
+        other_accepting: impl Fn(&S2) -> bool,
+    ) -> Result<(), Vec<L>> {
+        match self
+            .difference(other, is_accepting, other_accepting)
+            .shortest_accepted(|&v| v)
+        {
+            Some(witness) => Err(witness),
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the product of this DFA and `other`, which accepts exactly the strings accepted by
+    /// one but not both. It accepts nothing if and only if the two DFAs have the same language.
+    /// Each state holds whether it is accepting.
+    pub fn symmetric_difference<S2>(
+        &self,
+        other: &DFA<L, S2, H>,
+        is_accepting: impl Fn(&S) -> bool,
+        other_accepting: impl Fn(&S2) -> bool,
+    ) -> DFA<L, bool, H> {
+        self.product(other, true, |a, b| {
+            a.is_some_and(&is_accepting) != b.is_some_and(&other_accepting)
+        })
+    }
+
+    /// Renders the product of this DFA and `other` in the Graphviz DOT language, to show
+    /// where their languages differ. Each state is a pair of states, one from each DFA, with
+    /// a missing side for strings that fall off that DFA.
+    ///
+    /// Pairs accepted by both DFAs are double circles. Pairs accepted only by this DFA are
+    /// filled red, and pairs accepted only by `other` are filled blue, so equivalent DFAs
+    /// render with no filled states.
+    pub fn diff_dot<S2>(
+        &self,
+        other: &DFA<L, S2, H>,
+        is_accepting: impl Fn(&S) -> bool,
+        other_accepting: impl Fn(&S2) -> bool,
+    ) -> String
+    where
+        L: Display,
+    {
+        let product = self.product(other, true, |a, b| {
+            (
+                a.is_some_and(&is_accepting),
+                b.is_some_and(&other_accepting),
+            )
+        });
+
+        let mut dot = DotWriter::new([product.start]);
+
+        for (i, &(a, b)) in product.values.iter().enumerate() {
+            match (a, b) {
+                (true, false) => dot.filled_node(i, i, true, "red"),
+                (false, true) => dot.filled_node(i, i, true, "blue"),
+                _ => dot.node(i, i, a),
+            }
+        }
+
+        for (i, transitions) in product.transitions.iter().enumerate() {
+            let mut edges: Vec<(String, usize)> = transitions
+                .iter()
+                .map(|(symbol, &dest)| (symbol.to_string(), dest))
+                .collect();
+            edges.sort();
+            for (symbol, dest) in edges {
+                dot.edge(i, dest, symbol, false);
+            }
+        }
+
+        dot.finish()
+    }
+
+    /// Builds a DFA accepting exactly the strings over this DFA's alphabet that it rejects.
+    ///
+    /// Missing and dangling transitions are first sent to a new trap state, which is rejecting
+    /// here and therefore accepting in the complement. Each state holds whether it is accepting.
+    pub fn complement(self, is_accepting: impl Fn(&S) -> bool) -> DFA<L, bool, H> {
+        DFA {
+            values: self
+                .values
+                .iter()
+                .map(|value| !is_accepting(value))
+                .collect(),
+            transitions: self.transitions,
+            start: self.start,
+        }
+        .complete(true)
+    }
+
+    /// Completes this DFA over its alphabet by adding a transition for every state and symbol
+    /// that is missing one, all pointing at a new trap state holding `trap` that loops on every
+    /// symbol. Dangling transitions count as missing and are redirected to the trap too. No
+    /// state is added if the DFA is already complete.
+    pub fn complete(mut self, trap: S) -> DFA<L, S, H> {
+        let missing = self.missing_transitions();
+        if missing.is_empty() {
+            return self;
+        }
+
+        let alphabet: Vec<L> = self.alphabet().into_iter().cloned().collect();
+        let trap_index = self.values.len();
+
+        for (state, symbol) in missing {
+            self.transitions[state].insert(symbol, trap_index);
+        }
+
+        self.values.push(trap);
+        self.transitions.push(
+            alphabet
+                .into_iter()
+                .map(|symbol| (symbol, trap_index))
+                .collect(),
+        );
+
+        self
+    }
+
+    /// Lists every state and symbol of this DFA's alphabet that the state has no transition
+    /// on, in order of state. A dangling transition is listed as missing. The list is empty
+    /// exactly when this DFA is complete.
+    pub fn missing_transitions(&self) -> Vec<(usize, L)> {
+        let alphabet = self.alphabet();
+        let count = self.values.len();
+        self.transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(state, transitions)| {
+                alphabet
+                    .iter()
+                    .filter(move |&&symbol| {
+                        transitions.get(symbol).map_or(true, |&dest| dest >= count)
+                    })
+                    .map(move |&symbol| (state, symbol.clone()))
+            })
+            .collect()
+    }
+
+    /// Builds the product automaton of this DFA and `other` from their start states.
+    /// If `complete` is false, a symbol only has a transition if both components have one;
+    /// otherwise a component without one moves to an implicit trap state, passed to `value` as `None`.
+    fn product<S2, T, F>(&self, other: &DFA<L, S2, H>, complete: bool, mut value: F) -> DFA<L, T, H>
+    where
+        F: FnMut(Option<&S>, Option<&S2>) -> T,
+    {
+        let start = (Some(self.start), Some(other.start));
+        let mut indices = HashMap::from([(start, 0)]);
+        let mut pairs = vec![start];
+        let mut transitions = Vec::new();
+
+        // pairs are appended as they are discovered, so this visits every reachable pair once
+        let mut i = 0;
+        while i < pairs.len() {
+            let (a, b) = pairs[i];
+            let a_transitions = a.map(|a| &self.transitions[a]);
+            let b_transitions = b.map(|b| &other.transitions[b]);
+
+            let symbols = a_transitions.into_iter().flat_map(|trs| trs.keys()).chain(
+                b_transitions
+                    .into_iter()
+                    .flat_map(|trs| trs.keys())
+                    .filter(|&symbol| !a_transitions.is_some_and(|trs| trs.contains_key(symbol))),
+            );
+
+            let mut state_transitions = HashMap::default();
+
+            for symbol in symbols {
+                // a dangling transition rejects, just like a missing one
+                let a_dest = a_transitions
+                    .and_then(|trs| trs.get(symbol).copied())
+                    .filter(|&dest| dest < self.values.len());
+                let b_dest = b_transitions
+                    .and_then(|trs| trs.get(symbol).copied())
+                    .filter(|&dest| dest < other.values.len());
+
+                if (a_dest.is_none() && b_dest.is_none())
+                    || (!complete && (a_dest.is_none() || b_dest.is_none()))
+                {
+                    continue;
+                }
+
+                let dest = *indices.entry((a_dest, b_dest)).or_insert_with(|| {
+                    pairs.push((a_dest, b_dest));
+                    pairs.len() - 1
+                });
+
+                state_transitions.insert(symbol.clone(), dest);
+            }
+
+            transitions.push(state_transitions);
+            i += 1;
+        }
+
+        DFA {
+            values: pairs
+                .into_iter()
+                .map(|(a, b)| value(a.map(|a| &self.values[a]), b.map(|b| &other.values[b])))
+                .collect(),
+            transitions,
+            start: 0,
+        }
+    }
+}
+
+impl<L, T> DFA<L, (bool, T)> {
+    /// Creates a new DFA with a single start state holding `start`, along with whether it is
+    /// accepting. Its values implement [`Accepting`], so the `_default` methods need no
+    /// `is_accepting` closure.
+    pub fn new_with_accept(start: T, accepting: bool) -> Self {
+        Self::new((accepting, start))
+    }
+}
+
+/// The methods that take an `is_accepting` closure, deciding acceptance with [`Accepting`]
+/// instead.
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Eq + Hash,
+    S: Accepting,
+    H: BuildHasher,
+{
+    /// Like [`DFA::accepts`], with the accepting states given by their values.
+    pub fn accepts_default<I>(&self, inputs: I) -> bool
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        self.accepts(inputs, S::is_accepting)
+    }
+
+    /// Like [`DFA::longest_match`], with the accepting states given by their values.
+    pub fn longest_match_default<I>(&self, inputs: I) -> Option<(usize, State<'_, L, S, H>)>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        self.longest_match(inputs, S::is_accepting)
+    }
+
+    /// Like [`DFA::matches_prefix`], with the accepting states given by their values.
+    pub fn matches_prefix_default<I>(&self, inputs: I) -> Option<usize>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+    {
+        self.matches_prefix(inputs, S::is_accepting)
+    }
+
+    /// Like [`DFA::is_empty`], with the accepting states given by their values.
+    pub fn is_empty_default(&self) -> bool {
+        self.is_empty(S::is_accepting)
+    }
+
+    /// Like [`DFA::is_finite`], with the accepting states given by their values.
+    pub fn is_finite_default(&self) -> bool {
+        self.is_finite(S::is_accepting)
+    }
+
+    /// Like [`DFA::count_accepted`], with the accepting states given by their values.
+    pub fn count_accepted_default(&self, length: usize) -> u128 {
+        self.count_accepted(length, S::is_accepting)
+    }
+
+    /// Like [`DFA::remove_dead_states`], with the accepting states given by their values.
+    pub fn remove_dead_states_default(&mut self) {
+        self.remove_dead_states(S::is_accepting)
+    }
+
+    /// Like [`DFA::minimize`], with the accepting states given by their values.
+    pub fn minimize_default(self, merge: impl FnMut(Vec<S>) -> S) -> DFA<L, S, H>
+    where
+        H: Default,
+    {
+        self.minimize(S::is_accepting, merge)
+    }
+
+    /// Like [`DFA::shortest_accepted`], with the accepting states given by their values.
+    pub fn shortest_accepted_default(&self) -> Option<Vec<L>>
+    where
+        L: Clone,
+        H: Default,
+    {
+        self.shortest_accepted(S::is_accepting)
+    }
+
+    /// Like [`DFA::complement`], with the accepting states given by their values.
+    pub fn complement_default(self) -> DFA<L, bool, H>
+    where
+        L: Clone,
+        H: Default,
+    {
+        self.complement(S::is_accepting)
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Display,
+    S: Display,
+{
+    /// Renders this DFA in the Graphviz DOT language.
+    /// States are labeled with their index and value, and drawn as double circles if `is_accepting`.
+    ///
+    /// The edges of each state are sorted by their rendered symbol, so the same DFA always
+    /// renders identically. Dangling transitions are left out, as if they were missing.
+    pub fn to_dot(&self, is_accepting: impl Fn(&S) -> bool) -> String {
+        let mut dot = DotWriter::new([self.start]);
+
+        for (i, value) in self.values.iter().enumerate() {
+            dot.node(i, format!("{i}: {value}"), is_accepting(value));
+        }
+
+        for (i, transitions) in self.transitions.iter().enumerate() {
+            let mut edges: Vec<(String, usize)> = transitions
+                .iter()
+                .filter(|&(_, &dest)| dest < self.values.len())
+                .map(|(symbol, &dest)| (symbol.to_string(), dest))
+                .collect();
+            edges.sort();
+            for (symbol, dest) in edges {
+                dot.edge(i, dest, symbol, false);
+            }
+        }
+
+        dot.finish()
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Display,
+{
+    /// Renders this DFA as a Mermaid `stateDiagram-v2`, with state `i` named `Si`.
+    /// States that satisfy `is_accepting` get an arrow to the end marker `[*]`, and edges are
+    /// sorted and dangling transitions left out the same way as in [`DFA::to_dot`].
+    pub fn to_mermaid(&self, is_accepting: impl Fn(&S) -> bool) -> String {
+        let mut out = String::from("stateDiagram-v2\n");
+        out.push_str(&format!("    [*] --> S{}\n", self.start));
+
+        for (i, transitions) in self.transitions.iter().enumerate() {
+            let mut edges: Vec<(String, usize)> = transitions
+                .iter()
+                .filter(|&(_, &dest)| dest < self.values.len())
+                .map(|(symbol, &dest)| (symbol.to_string(), dest))
+                .collect();
+            edges.sort();
+            for (symbol, dest) in edges {
+                out.push_str(&format!("    S{i} --> S{dest}: {symbol}\n"));
+            }
+        }
+
+        for (i, value) in self.values.iter().enumerate() {
+            if is_accepting(value) {
+                out.push_str(&format!("    S{i} --> [*]\n"));
+            }
+        }
+
+        out
+    }
+}
+
+impl<L, S, H> DFA<L, S, H>
+where
+    L: Ord,
+{
+    /// Renumbers the states in breadth-first order from the start, following the transitions
+    /// of each state in sorted symbol order. Unreachable states keep their relative order
+    /// after the reachable ones. DFAs that only differ in how their states were numbered are
+    /// equal afterwards. Returns the new index of every old state.
+    pub fn canonicalize(&mut self) -> Vec<usize> {
+        let n = self.values.len();
+        let mut order = vec![self.start];
+        let mut seen = vec![false; n];
+        seen[self.start] = true;
+
+        let mut i = 0;
+        while i < order.len() {
+            let mut edges: Vec<(&L, usize)> = self.transitions[order[i]]
+                .iter()
+                .map(|(symbol, &dest)| (symbol, dest))
+                .collect();
+            edges.sort();
+            for (_, dest) in edges {
+                if dest < n && !seen[dest] {
+                    seen[dest] = true;
+                    order.push(dest);
+                }
+            }
+            i += 1;
+        }
+        order.extend((0..n).filter(|&state| !seen[state]));
+
+        let mut reassignments = vec![0; n];
+        for (new, &old) in order.iter().enumerate() {
+            reassignments[old] = new;
+        }
+
+        let mut values: Vec<Option<S>> = core::mem::take(&mut self.values)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut transitions: Vec<Option<HashMap<L, usize, H>>> =
+            core::mem::take(&mut self.transitions)
+                .into_iter()
+                .map(Some)
+                .collect();
+
+        for &old in order.iter() {
+            let mut state_transitions = transitions[old].take().unwrap();
+            for dest in state_transitions.values_mut() {
+                // dangling transitions stay dangling
+                if let Some(&new) = reassignments.get(*dest) {
+                    *dest = new;
+                }
+            }
+            self.values.push(values[old].take().unwrap());
+            self.transitions.push(state_transitions);
+        }
+        self.start = 0;
+
+        reassignments
+    }
+}
+
+/// Determinizes the reverse of a DFA whose values say whether each state is accepting.
+///
+/// The reverse starts from every accepting state at once rather than from a new start state,
+/// so that determinizing the reverse of an accessible DFA gives a minimal one.
+fn reverse_determinize<L, H>(dfa: &DFA<L, bool, H>) -> DFA<L, bool>
+where
+    L: Clone + Eq + Hash,
+{
+    let count = dfa.values.len();
+    let starts: Vec<usize> = (0..count).filter(|&i| dfa.values[i]).collect();
+    if starts.is_empty() {
+        return DFA::new(false);
+    }
+
+    let mut states: Vec<_> = (0..count)
+        .map(|i| crate::state::State::new(i == dfa.start, ()))
+        .collect();
+    for (from, transitions) in dfa.transitions.iter().enumerate() {
+        for (symbol, &to) in transitions.iter() {
+            if to < count {
+                states[to].add_transition(RealTransition::new(symbol.clone(), from));
+            }
+        }
+    }
+
+    NFA { states, starts }.into_dfa_with(|states| states.iter().any(|st| st.is_finish()))
+}
+
+/// Renders a transition table with one row per state and one column per symbol, in sorted
+/// order. The start state is marked with `>`, and missing and dangling transitions are left
+/// blank.
+impl<L, S, H> Display for DFA<L, S, H>
+where
+    L: Display + Ord,
+    S: Display,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut symbols: Vec<&L> = self.transitions.iter().flat_map(|t| t.keys()).collect();
+        symbols.sort();
+        symbols.dedup();
+
+        let mut rows: Vec<Vec<String>> = Vec::with_capacity(self.values.len() + 1);
+        let mut header = vec![String::new(), String::new()];
+        header.extend(symbols.iter().map(|symbol| symbol.to_string()));
+        rows.push(header);
+
+        for (i, (value, transitions)) in self.values.iter().zip(&self.transitions).enumerate() {
+            let marker = if i == self.start { ">" } else { " " };
+            let mut row = vec![format!("{marker}{i}"), value.to_string()];
+            for symbol in symbols.iter() {
+                // symbols are only compared by `Ord` here, so find the key without hashing
+                let dest = transitions
+                    .iter()
+                    .find(|(key, _)| key.cmp(symbol).is_eq())
+                    .filter(|&(_, &dest)| dest < self.values.len())
+                    .map(|(_, dest)| dest.to_string());
+                row.push(dest.unwrap_or_default());
+            }
+            rows.push(row);
+        }
+
+        let mut widths = vec![0; symbols.len() + 2];
+        for row in rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        for row in rows.iter() {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:<width$}"))
+                .collect();
+            writeln!(f, "{}", line.join(" | ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, L, S, H> State<'a, L, S, H> {
+    fn new(dfa: &'a DFA<L, S, H>, index: usize) -> Self {
+        debug_assert!(index < dfa.values.len());
+        State { dfa, index }
+    }
+
+    /// Gets the index of this state in its DFA.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the value held by this state.
+    pub fn value(&self) -> &'a S {
+        &self.dfa.values[self.index]
+    }
+
+    /// Iterates over the symbol and destination of every transition from this state.
+    pub fn transitions(&self) -> impl Iterator<Item = (&'a L, usize)> {
+        self.dfa.transitions[self.index]
+            .iter()
+            .map(|(symbol, &dest)| (symbol, dest))
+    }
+}
+
+impl<'a, L, S, H> State<'a, L, S, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Gets the index of the state that the given symbol transitions from this state to.
+    pub fn get_transition(&self, symbol: &L) -> Option<usize> {
+        self.dfa
+            .get_state_transitions(self.index)?
+            .get(symbol)
+            .copied()
+    }
+
+    /// Finds the state that the given symbol transitions from this state to.
+    pub fn next(&self, symbol: &L) -> Option<State<'a, L, S, H>> {
+        self.dfa.get_state(self.get_transition(symbol)?)
+    }
+}
+
+impl<'a, L, S, H> DFAMatcher<'a, L, S, H> {
+    /// Gets the state the matcher is in, or `None` if an earlier symbol had no transition.
+    pub fn current(&self) -> Option<State<'a, L, S, H>> {
+        self.dfa.get_state(self.current?)
+    }
+
+    /// Returns whether the matcher is in a state satisfying `is_accepting`.
+    pub fn is_accepting(&self, is_accepting: impl Fn(&S) -> bool) -> bool {
+        self.current()
+            .is_some_and(|state| is_accepting(state.value()))
+    }
+
+    /// Moves the matcher back to the start state.
+    pub fn reset(&mut self) {
+        self.current = Some(self.dfa.start);
+    }
+}
+
+impl<'a, L, S, H> DFAMatcher<'a, L, S, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Follows the transition for the given symbol.
+    /// Returns false if there is none, after which the matcher stays stuck until reset.
+    pub fn feed(&mut self, symbol: &L) -> bool {
+        self.current = self
+            .current()
+            .and_then(|state| state.next(symbol))
+            .map(|state| state.index());
+        self.current.is_some()
+    }
+}
+
+impl<'a, L, S, H> MutState<'a, L, S, H> {
+    fn new(dfa: &'a mut DFA<L, S, H>, index: usize) -> Self {
+        debug_assert!(index < dfa.values.len());
+        MutState { dfa, index }
+    }
+
+    /// Gets the index of this state in its DFA.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Replaces the value held by this state.
+    pub fn set_value(self, value: S) -> Self {
+        self.dfa.values[self.index] = value;
+        self
+    }
+}
+
+impl<'a, L, S, H> MutState<'a, L, S, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Sets the destination of the transition from this state on the given symbol,
+    /// overwriting any existing one.
+    pub fn set_transition(self, transition: L, to: usize) -> Self {
+        self.dfa.transitions[self.index].insert(transition, to);
+        self
+    }
+
+    /// Sets the destination of every given transition from this state, overwriting existing
+    /// ones. A symbol that appears more than once leads to its last destination.
+    pub fn set_transitions<I>(self, transitions: I) -> Self
+    where
+        I: IntoIterator<Item = (L, usize)>,
+    {
+        self.dfa.transitions[self.index].extend(transitions);
+        self
+    }
+
+    /// Sets the destination of the transition from this state on the given symbol, failing
+    /// if the symbol already leads to a different state.
+    pub fn try_set_transition(self, transition: L, to: usize) -> Result<Self, DeterminismError> {
+        let existing = *self.dfa.transitions[self.index]
+            .entry(transition)
+            .or_insert(to);
+        if existing != to {
+            return Err(DeterminismError { state: self.index });
+        }
+        Ok(self)
+    }
+
+    /// Removes the transition from this state on the given symbol, if there is one.
+    pub fn remove_transition(self, transition: &L) -> Self {
+        self.dfa.transitions[self.index].remove(transition);
+        self
+    }
+}
+
+#[test]
+fn new() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    assert!(dfa.traverse("//  ".chars()).is_some_and(|x| *x.value()));
+    assert!(!dfa.traverse("/".chars()).is_some_and(|x| *x.value()));
+    assert!(dfa.traverse("/ ".chars()).is_none());
+}
+
+#[test]
+fn test_literal() {
+    let dfa = DFA::literal("ab".chars());
+    assert_eq!(dfa.state_count(), 3);
+    assert!(dfa.accepts("ab".chars(), |&v| v));
+    for input in ["", "a", "abc", "ba", "b"] {
+        assert!(!dfa.accepts(input.chars(), |&v| v), "{}", input);
+    }
+
+    let empty = DFA::literal("".chars());
+    assert!(empty.accepts("".chars(), |&v| v));
+    assert!(!empty.accepts("a".chars(), |&v| v));
+}
+
+#[test]
+fn test_accepting() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    assert!(dfa.is_accepting(2));
+    assert!(!dfa.is_accepting(0));
+    assert!(!dfa.is_accepting(3));
+    assert!(dfa.accepts("//".chars(), Accepting::is_accepting));
+    assert!(!dfa.accepts("/".chars(), Accepting::is_accepting));
+
+    assert!(dfa.set_accepting(1, true));
+    assert!(!dfa.set_accepting(3, true));
+    assert!(dfa.accepts("/".chars(), Accepting::is_accepting));
+
+    // the values made by determinizing an NFA carry the flag too
+    let mut determinized = dfa.map_values(|accepting| (accepting, ()));
+    assert!(determinized.is_accepting(1));
+    determinized.set_accepting(1, false);
+    assert!(!determinized.accepts("/".chars(), Accepting::is_accepting));
+}
+
+#[test]
+fn test_accepting_defaults() {
+    // "//" followed by spaces, where each state is named
+    let mut dfa = DFA::new_with_accept("start", false);
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state((false, "slash")).set_transition('/', 2);
+    dfa.add_state((false, "comment")).set_transition(' ', 2);
+    dfa.add_state((false, "unreachable"));
+
+    assert!(dfa.is_empty_default());
+    assert_eq!(dfa.shortest_accepted_default(), None);
+    assert!(dfa.set_accepting(2, true));
+
+    assert!(!dfa.is_empty_default());
+    assert!(dfa.accepts_default("// ".chars()));
+    assert!(!dfa.accepts_default("/".chars()));
+    assert!(!dfa.is_finite_default());
+    assert_eq!(dfa.count_accepted_default(4), 1);
+    assert_eq!(dfa.shortest_accepted_default(), Some(vec!['/', '/']));
+    assert_eq!(dfa.matches_prefix_default("//x".chars()), Some(2));
+    let (length, state) = dfa.longest_match_default("//  x".chars()).unwrap();
+    assert_eq!((length, state.value().1), (4, "comment"));
+
+    let complement = dfa.clone().complement_default();
+    assert!(complement.accepts_default("/".chars()));
+    assert!(!complement.accepts_default("//".chars()));
+
+    let mut trimmed = dfa.clone();
+    trimmed.remove_dead_states_default();
+    assert_eq!(trimmed.state_count(), 3);
+    let minimal = dfa.minimize_default(|mut values| values.remove(0));
+    assert_eq!(minimal.state_count(), 3);
+    assert!(minimal.accepts_default("//".chars()));
+
+    // an existing DFA of flags needs no conversion, and any other can be paired with them
+    let flags = DFA::from_str_table("0 -a-> 1\naccept: 1").unwrap();
+    assert!(flags.accepts_default("a".chars()));
+    let named = flags.map_values(|v| if v { "yes" } else { "no" });
+    let paired = named.with_accepting(|&v| v == "yes");
+    assert!(paired.accepts_default("a".chars()));
+    assert!(!paired.accepts_default("".chars()));
+}
+
+#[test]
+fn test_universal() {
+    let universal = DFA::universal(['a', 'b']);
+    let empty = DFA::empty_language(['a', 'b']);
+
+    for input in ["", "a", "ab", "bba", "abab"] {
+        assert!(universal.accepts(input.chars(), |&v| v), "{}", input);
+        assert!(!empty.accepts(input.chars(), |&v| v), "{}", input);
+    }
+    assert!(!universal.accepts("abc".chars(), |&v| v));
+    assert!(universal.stats(|&v| v).is_complete);
+    assert!(empty.is_empty(|&v| v));
+
+    // identities of intersection and union
+    let dfa = DFA::literal("ab".chars());
+    let meet = dfa.intersect(&universal);
+    let join = dfa.union(&empty);
+    for input in ["", "a", "ab", "ba"] {
+        let expected = dfa.accepts(input.chars(), |&v| v);
+        assert_eq!(meet.accepts(input.chars(), |&(a, b)| a && b), expected);
+        assert_eq!(
+            join.accepts(input.chars(), |&(a, b)| a == Some(true) || b == Some(true)),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_traverse_with_path() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    let (end, path) = dfa.traverse_with_path("//  ".chars()).unwrap();
+    assert!(*end.value());
+    assert_eq!(path, vec![0, 1, 2, 2, 2]);
+
+    let (end, path) = dfa.traverse_with_path("".chars()).unwrap();
+    assert_eq!(end.index(), 0);
+    assert_eq!(path, vec![0]);
+
+    assert!(dfa.traverse_with_path("/ ".chars()).is_none());
+}
+
+#[test]
+fn test_set_transitions() {
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 0)
+        .set_transitions([('a', 1), ('b', 2), ('c', 3)]);
+    dfa.add_state(true);
+    dfa.add_state(true);
+    dfa.add_state(true);
+
+    for (input, index) in [("a", 1), ("b", 2), ("c", 3)] {
+        assert_eq!(
+            dfa.traverse(input.chars()).map(|st| st.index()),
+            Some(index)
+        );
+    }
+    assert_eq!(dfa.get_start().transitions().count(), 3);
+}
+
+#[test]
+fn test_set_value() {
+    let mut dfa = DFA::new(0);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_value(1)
+        .set_transition('a', 1);
+    dfa.add_state(2);
+    assert_eq!(*dfa.get_start().value(), 1);
+
+    *dfa.get_state_value_mut(0).unwrap() += 10;
+    assert_eq!(*dfa.get_start().value(), 11);
+    assert_eq!(dfa.traverse("a".chars()).map(|st| *st.value()), Some(2));
+    assert!(dfa.get_state_value_mut(2).is_none());
+}
+
+#[test]
+fn test_traverse_convenience() {
+    // symbols only need `Eq + Hash`, not `Debug`
+    #[derive(PartialEq, Eq, Hash)]
+    struct Token(u8);
+
+    let mut tokens = DFA::new(false);
+    tokens.get_state_mut(0).unwrap().set_transition(Token(1), 1);
+    tokens.add_state(true);
+    assert!(tokens.accepts([Token(1)].iter(), |&v| v));
+    assert!(tokens.traverse([Token(2)].into_iter()).is_none());
+
+    let mut bytes = DFA::new(false);
+    bytes.get_state_mut(0).unwrap().set_transition(b'a', 1);
+    bytes.add_state(true).set_transition(b'b', 0);
+    assert_eq!(bytes.traverse_bytes(b"aba").map(|st| st.index()), Some(1));
+    assert!(bytes.traverse_bytes(b"b").is_none());
+
+    let dfa = bytes.map_labels(char::from).unwrap();
+    assert_eq!(dfa.traverse_str("ab").map(|st| st.index()), Some(0));
+    assert!(dfa.traverse_str("abb").is_none());
+}
+
+#[test]
+fn test_traverse_detailed() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    assert_eq!(
+        dfa.traverse_detailed("//  ".chars()).map(|st| st.index()),
+        Ok(2)
+    );
+    assert_eq!(
+        dfa.traverse_detailed("///".chars()).map(|st| st.index()),
+        Err(2)
+    );
+    assert_eq!(
+        dfa.traverse_detailed("".chars()).map(|st| st.index()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn test_from_parts() {
+    let dfa = DFA::from_parts(
+        vec![false, false, true],
+        vec![(0, 'a', 1), (1, 'b', 2), (2, 'a', 1), (0, 'a', 1)],
+        0,
+    )
+    .unwrap();
+
+    assert_eq!(dfa.state_count(), 3);
+    assert!(dfa.accepts("abab".chars(), |&v| v));
+    assert!(!dfa.accepts("aba".chars(), |&v| v));
+
+    assert_eq!(
+        DFA::from_parts(vec![false, true], vec![(0, 'a', 1), (0, 'a', 0)], 0).err(),
+        Some(BuildError::Nondeterministic(DeterminismError { state: 0 }))
+    );
+    assert_eq!(
+        DFA::from_parts(vec![false, true], vec![(0, 'a', 2)], 0).err(),
+        Some(BuildError::InvalidTransitionTarget(2))
+    );
+    assert_eq!(
+        DFA::<char, bool>::from_parts(vec![false], vec![], 1).err(),
+        Some(BuildError::InvalidStartIndex(1))
+    );
+}
+
+#[test]
+fn test_from_keywords() {
+    let dfa = DFA::from_keywords(["if", "in", "int"]);
+
+    // the start, the shared "i", and one state for each keyword
+    assert_eq!(dfa.state_count(), 5);
+    for keyword in ["if", "in", "int"] {
+        let state = dfa.traverse(keyword.chars()).unwrap();
+        assert_eq!(state.value().as_deref(), Some(keyword));
+    }
+    assert!(!dfa.accepts("i".chars(), Option::is_some));
+    assert!(!dfa.accepts("ix".chars(), Option::is_some));
+    assert!(!dfa.accepts("".chars(), Option::is_some));
+}
+
+#[test]
+fn test_matches_prefix() {
+    let dfa = DFA::from_str_table("0 -a-> 1\n1 -b-> 2\n2 -c-> 3\naccept: 2 3").unwrap();
+
+    assert_eq!(dfa.matches_prefix("abcd".chars(), |&v| v), Some(2));
+    assert_eq!(dfa.longest_match("abcd".chars(), |&v| v).unwrap().0, 3);
+    assert_eq!(dfa.matches_prefix("a".chars(), |&v| v), None);
+    assert_eq!(dfa.matches_prefix("ax".chars(), |&v| v), None);
+    assert_eq!(dfa.matches_prefix("xyz".chars(), |&v| !v), Some(0));
+}
+
+#[test]
+fn test_stats() {
+    let dfa = DFA::from_str_table("0 -a-> 1\n0 -b-> 0\n1 -a-> 1\n1 -b-> 2\naccept: 2").unwrap();
+    let stats = dfa.stats(|&v| v);
+    assert_eq!(
+        stats,
+        AutomatonStats {
+            num_states: 3,
+            num_transitions: 4,
+            num_epsilon_transitions: 0,
+            num_accepting: 1,
+            is_complete: false,
+            max_out_degree: 2,
+        }
+    );
+
+    let complete = dfa.complete(false);
+    let stats = complete.stats(|&v| v);
+    assert!(stats.is_complete);
+    assert_eq!(stats.num_states, 4);
+    assert_eq!(stats.num_transitions, 8);
+}
+
+#[test]
+fn test_share_labels() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct Label(String);
+
+    impl Clone for Label {
+        fn clone(&self) -> Self {
+            CLONES.fetch_add(1, Ordering::Relaxed);
+            Label(self.0.clone())
+        }
+    }
+
+    // ("if" | "else")* and "if" "else"
+    let keyword = |s: &str| Label(s.to_string());
+    let mut many = DFA::new(true);
+    many.get_state_mut(0)
+        .unwrap()
+        .set_transition(keyword("if"), 0)
+        .set_transition(keyword("else"), 0);
+    let mut pair = DFA::new(false);
+    pair.get_state_mut(0)
+        .unwrap()
+        .set_transition(keyword("if"), 1);
+    pair.add_state(false).set_transition(keyword("else"), 2);
+    pair.add_state(true);
+
+    let before = CLONES.load(Ordering::Relaxed);
+    many.intersect(&pair);
+    assert!(CLONES.load(Ordering::Relaxed) > before);
+
+    let many = many.share_labels();
+    let pair = pair.share_labels();
+    let before = CLONES.load(Ordering::Relaxed);
+    let both: SharedDFA<Label, (bool, bool)> = many.intersect(&pair);
+    assert_eq!(CLONES.load(Ordering::Relaxed), before);
+
+    let input = [Rc::new(keyword("if")), Rc::new(keyword("else"))];
+    assert!(both.accepts(input.iter(), |&(a, b)| a && b));
+    assert!(!both.accepts(input[..1].iter(), |&(a, b)| a && b));
+}
+
+#[test]
+fn test_into_parts() {
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    let (values, transitions, start) = dfa.clone().into_parts();
+    assert_eq!(values, vec![false, false, true]);
+    assert_eq!(start, 0);
+    let edges: Vec<(usize, char, usize)> = transitions
+        .iter()
+        .enumerate()
+        .flat_map(|(from, trs)| trs.iter().map(move |(&symbol, &to)| (from, symbol, to)))
+        .collect();
+    let rebuilt = DFA::from_parts(values, edges, start).unwrap();
+    assert_eq!(rebuilt, dfa);
+    for input in ["//  ", "//", "/", "/ "] {
+        assert_eq!(
+            rebuilt.accepts(input.chars(), |&v| v),
+            dfa.accepts(input.chars(), |&v| v)
+        );
+    }
+
+    let states: Vec<(bool, usize)> = dfa
+        .into_iter()
+        .map(|(value, transitions)| (value, transitions.len()))
+        .collect();
+    assert_eq!(states, vec![(false, 1), (false, 1), (true, 1)]);
+}
+
+#[test]
+fn test_into_nfa() {
+    use crate::nfae::NFAe;
+
+    let dfa = DFA::from_str_table("0 -a-> 1\n1 -b-> 0\n1 -c-> 2\naccept: 1").unwrap();
+    let mut dangling = dfa.clone();
+    dangling.get_state_mut(1).unwrap().set_transition('d', 9);
+
+    let nfa = dangling.clone().into_nfa(|&v| v);
+    for input in ["a", "aba", "ab", "", "ac", "ad"] {
+        let symbols: Vec<char> = input.chars().collect();
+        assert_eq!(
+            nfa.accepts(symbols.iter()),
+            dangling.accepts(input.chars(), |&v| v),
+            "{}",
+            input
+        );
+    }
+
+    // the lifted NFA can be combined like any other
+    let starred = NFAe::from(nfa).star().into_nfa().unwrap();
+    assert!(starred.accepts(['a', 'a', 'b', 'a'].iter()));
+    assert!(starred.accepts([].iter()));
+    assert!(!starred.accepts(['b'].iter()));
+}
+
+#[test]
+fn test_clone_eq() {
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(true).set_transition('b', 0);
+
+    let clone = dfa.clone();
+    assert_eq!(clone, dfa);
+
+    let complement = clone.complement(|&v| v);
+    assert_ne!(complement, dfa);
+    assert!(format!("{:?}", dfa).contains("DFA"));
+}
+
+#[test]
+fn test_display_table() {
+    let mut dfa = DFA::new("start");
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('b', 1)
+        .set_transition('a', 0);
+    dfa.add_state("end");
+
+    let table = dfa.to_string();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "   |       | a | b");
+    assert_eq!(lines[1], ">0 | start | 0 | 1");
+    assert_eq!(lines[2], " 1 | end   |   |");
+
+    // a dangling transition is left blank rather than naming a state that does not exist
+    dfa.get_state_mut(1).unwrap().set_transition('a', 7);
+    let table = dfa.to_string();
+    assert_eq!(table.lines().nth(2), Some(" 1 | end   |   |"));
+    assert!(!table.contains('7'));
+}
+
+#[test]
+fn test_from_str_table() {
+    let dfa = DFA::from_str_table(
+        "# strings ending in 'ab'
+        start: 0
+        0 -a-> 1
+        0 -b-> 0
+        1 -a-> 1
+        1 -b-> 2
+        2 -a-> 1
+        2 -b-> 0
+        accept: 2",
+    )
+    .unwrap();
+    assert_eq!(dfa.state_count(), 3);
+    assert!(dfa.accepts("bbab".chars(), |&v| v));
+    assert!(!dfa.accepts("aba".chars(), |&v| v));
+
+    // the table printed by `Display` parses back to the same DFA
+    assert_eq!(DFA::from_str_table(&dfa.to_string()), Ok(dfa.clone()));
+    let mut other = dfa.clone();
+    other.start = 2;
+    assert_eq!(DFA::from_str_table(&other.to_string()), Ok(other));
+
+    let error = |input| DFA::from_str_table(input).unwrap_err();
+    assert_eq!(
+        error("0 -a-> 1\n0 -ab-> 1"),
+        ParseError {
+            line: 2,
+            kind: ParseErrorKind::Malformed
+        }
+    );
+    assert_eq!(error("0 -a-> 1\n\n0 -a-> 2").line, 3);
+    assert_eq!(
+        error("0 -a-> 1\n\n0 -a-> 2").kind,
+        ParseErrorKind::Nondeterministic
+    );
+    assert_eq!(
+        error("start: 0\nstart: 1").kind,
+        ParseErrorKind::DuplicateStart
+    );
+    assert_eq!(error("accept: 1, x").kind, ParseErrorKind::InvalidState);
+    assert_eq!(
+        error("  | | a\n>0 | true | 1").kind,
+        ParseErrorKind::InvalidState
+    );
+    assert_eq!(
+        error("  | | a\n>0 | yes | 0").kind,
+        ParseErrorKind::InvalidValue
+    );
+    assert_eq!(error("0 -a-> x").to_string(), "line 1: invalid state");
+
+    // states must be numbered without gaps, however large the number
+    assert_eq!(
+        error("0 -a-> 1\n1 -b-> 3"),
+        ParseError {
+            line: 2,
+            kind: ParseErrorKind::InvalidState
+        }
+    );
+    assert_eq!(error("start: 18446744073709551615").line, 1);
+    assert_eq!(
+        error("0 -a-> 1\nstart: 4000000000").kind,
+        ParseErrorKind::InvalidState
+    );
+    assert_eq!(error("0 -a-> 0\naccept: 0 9").line, 2);
+
+    // a '|' symbol does not make an edge list a table
+    let pipes = DFA::from_str_table("0 -|-> 1\n1 -a-> 0\naccept: 1").unwrap();
+    assert!(pipes.accepts("|a|".chars(), |&v| v));
+    assert!(!pipes.accepts("|a".chars(), |&v| v));
+}
+
+#[test]
+fn test_custom_hasher() {
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // a deterministic hasher that only looks at the last value written
+    #[derive(Default)]
+    struct LastHasher(u64);
+
+    impl Hasher for LastHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = (self.0 << 8) | byte as u64;
+            }
+        }
+    }
+
+    let mut dfa: DFA<char, bool, BuildHasherDefault<LastHasher>> = DFA::new_with_hasher(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 0)
+        .set_transition('b', 1);
+    dfa.add_state(true).set_transition('a', 1);
+
+    assert!(dfa.accepts("aaba".chars(), |&v| v));
+    assert!(!dfa.accepts("abb".chars(), |&v| v));
+
+    let minimized = dfa.complement(|&v| v).minimize(|&v| v, |values| values[0]);
+    assert!(minimized.accepts("abb".chars(), |&v| v));
+}
+
+#[test]
+fn test_map_values() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    let dfa = dfa.map_values(|v| if v { 7u8 } else { 0 });
+
+    assert_eq!(*dfa.traverse("//".chars()).unwrap().value(), 7);
+    assert_eq!(*dfa.traverse("/".chars()).unwrap().value(), 0);
+}
+
+#[test]
+fn test_map_labels() {
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('A', 1)
+        .set_transition('a', 1)
+        .set_transition('b', 1);
+    dfa.add_state(true);
+
+    let lowered = dfa.map_labels(|c| c.to_ascii_lowercase()).unwrap();
+    assert!(lowered.accepts("a".chars(), |&v| v));
+    assert!(lowered.accepts("b".chars(), |&v| v));
+    assert!(!lowered.accepts("A".chars(), |&v| v));
+
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('A', 1)
+        .set_transition('a', 2);
+    dfa.add_state(true);
+    dfa.add_state(true);
+
+    assert_eq!(
+        dfa.map_labels(|c| c.to_ascii_lowercase()).err(),
+        Some(DeterminismError { state: 0 })
+    );
+}
+
+#[test]
+fn test_iter_states() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true)
+        .set_transition(' ', 2)
+        .set_transition('\t', 2);
+
+    assert_eq!(dfa.iter_states().count(), 3);
+    assert_eq!(
+        dfa.iter_states()
+            .map(|(_, _, trs)| trs.len())
+            .sum::<usize>(),
+        4
+    );
+    assert_eq!(
+        dfa.iter_states()
+            .filter(|&(_, &value, _)| value)
+            .map(|(i, _, _)| i)
+            .collect::<Vec<_>>(),
+        vec![2]
+    );
+
+    let mut transitions: Vec<_> = dfa.get_state(2).unwrap().transitions().collect();
+    transitions.sort();
+    assert_eq!(transitions, vec![(&'\t', 2), (&' ', 2)]);
+}
+
+#[test]
+fn test_remove_transition() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 1)
+        .remove_transition(&'a')
+        .remove_transition(&'c');
+    dfa.add_state(true);
+
+    assert_eq!(dfa.get_start().get_transition(&'a'), None);
+    assert_eq!(dfa.get_start().get_transition(&'b'), Some(1));
+    assert!(dfa.traverse("a".chars()).is_none());
+    assert!(dfa.accepts("b".chars(), |&v| v));
+}
+
+#[test]
+fn test_try_set_transition() {
+    let mut dfa = DFA::new(false);
+    dfa.add_state(false);
+    dfa.add_state(true);
+
+    let state = dfa
+        .get_state_mut(0)
+        .unwrap()
+        .try_set_transition('a', 1)
+        .and_then(|st| st.try_set_transition('a', 1))
+        .unwrap();
+    assert_eq!(
+        state.try_set_transition('a', 2).err(),
+        Some(DeterminismError { state: 0 })
+    );
+    assert_eq!(dfa.get_start().get_transition(&'a'), Some(1));
+}
+
+#[test]
+fn test_remove_state() {
+    let mut dfa = DFA::new("start");
+
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 2);
+    dfa.add_state("a").set_transition('c', 3);
+    dfa.add_state("b").set_transition('c', 3);
+    dfa.add_state("c");
+
+    assert_eq!(dfa.remove_state(0), None);
+    assert_eq!(dfa.remove_state(4), None);
+    assert_eq!(dfa.remove_state(1), Some("a"));
+
+    assert_eq!(dfa.state_count(), 3);
+    assert!(dfa.traverse("a".chars()).is_none());
+    assert_eq!(*dfa.traverse("b".chars()).unwrap().value(), "b");
+    assert_eq!(*dfa.traverse("bc".chars()).unwrap().value(), "c");
+}
+
+#[test]
+fn test_accepts() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    assert!(dfa.accepts("//  ".chars(), |&v| v));
+    assert!(dfa.accepts("//".chars(), |&v| v));
+    assert!(!dfa.accepts("/".chars(), |&v| v));
+    assert!(!dfa.accepts("/ ".chars(), |&v| v));
+}
+
+#[test]
+fn test_is_empty() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(false).set_transition('a', 0);
+    dfa.add_state(true);
+
+    assert!(dfa.is_empty(|&v| v));
+
+    dfa.get_state_mut(1).unwrap().set_transition('b', 2);
+
+    assert!(!dfa.is_empty(|&v| v));
+}
+
+#[test]
+fn test_count_accepted() {
+    // accepts everything over {a, b}
+    let mut dfa = DFA::new(true);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 0)
+        .set_transition('b', 0);
+
+    for length in 0..10 {
+        assert_eq!(dfa.count_accepted(length, |&v| v), 1 << length);
+    }
+    assert_eq!(dfa.count_accepted(200, |&v| v), u128::MAX);
+
+    // strings ending in "b", with a dangling transition on 'c'
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 0)
+        .set_transition('b', 1)
+        .set_transition('c', 9);
+    dfa.add_state(true)
+        .set_transition('a', 0)
+        .set_transition('b', 1);
+
+    assert_eq!(dfa.count_accepted(0, |&v| v), 0);
+    assert_eq!(dfa.count_accepted(1, |&v| v), 1);
+    assert_eq!(dfa.count_accepted(3, |&v| v), 4);
+    assert_eq!(dfa.count_accepted(usize::MAX, |&v| v), u128::MAX);
+
+    // a*b*, whose count grows without saturating, on either side of the switch to squaring
+    let ab = DFA::from_str_table("0 -a-> 0\n0 -b-> 1\n1 -b-> 1\naccept: 0 1").unwrap();
+    for length in 0..10 {
+        assert_eq!(ab.count_accepted(length, |&v| v), length as u128 + 1);
+    }
+    assert_eq!(
+        ab.count_accepted(usize::MAX, |&v| v),
+        usize::MAX as u128 + 1
+    );
+
+    // (aa)*, whose counts alternate forever
+    let even = DFA::from_str_table("0 -a-> 1\n1 -a-> 0\naccept: 0").unwrap();
+    assert_eq!(even.count_accepted(usize::MAX, |&v| v), 0);
+    assert_eq!(even.count_accepted(usize::MAX - 1, |&v| v), 1);
+    assert_eq!(even.count_accepted(1000, |&v| v), 1);
+
+    // "ab" and "ac", where a dead sink and an unreachable cycle leave the useful part acyclic
+    let finite = DFA::from_str_table(
+        "0 -a-> 1\n1 -b-> 2\n1 -c-> 2\n0 -x-> 3\n3 -x-> 3\n4 -a-> 4\n4 -b-> 2\naccept: 2",
+    )
+    .unwrap();
+    assert_eq!(finite.count_accepted(2, |&v| v), 2);
+    assert_eq!(finite.count_accepted(1, |&v| v), 0);
+    assert_eq!(finite.count_accepted(usize::MAX, |&v| v), 0);
+}
+
+#[test]
+fn test_sccs() {
+    // a self-loop on 1 and a cycle between 2 and 3
+    let mut dfa = DFA::new(());
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(())
+        .set_transition('a', 1)
+        .set_transition('b', 2);
+    dfa.add_state(()).set_transition('a', 3);
+    dfa.add_state(())
+        .set_transition('a', 2)
+        .set_transition('b', 4);
+    dfa.add_state(());
+
+    let sccs = dfa.sccs();
+    assert_eq!(sccs, vec![vec![4], vec![2, 3], vec![1], vec![0]]);
+}
+
+#[test]
+fn test_topo_order() {
+    // "abc" and "ac", built out of order, with a dangling transition on 'x'
+    let mut dfa = DFA::from_parts(
+        vec![false, true, false, false],
+        vec![(0, 'a', 3), (3, 'b', 2), (2, 'c', 1), (3, 'c', 1)],
+        0,
+    )
+    .unwrap();
+    dfa.get_state_mut(1).unwrap().set_transition('x', 9);
+
+    let order = dfa.topo_order().unwrap();
+    assert_eq!(order, vec![0, 3, 2, 1]);
+    assert_eq!(dfa.count_accepted(2, |&v| v), 1);
+    assert_eq!(dfa.count_accepted(usize::MAX, |&v| v), 0);
+
+    dfa.get_state_mut(2).unwrap().set_transition('b', 2);
+    assert_eq!(dfa.topo_order(), Err(CycleError { state: 2 }));
+    // "abbbc" now has enough symbols to visit a state twice
+    assert_eq!(dfa.count_accepted(5, |&v| v), 1);
+}
+
+#[test]
+fn test_is_finite() {
+    // accepts "abc"
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(false).set_transition('b', 2);
+    dfa.add_state(false).set_transition('c', 3);
+    dfa.add_state(true).set_transition('x', 4);
+    // a loop that cannot lead to acceptance does not count
+    dfa.add_state(false).set_transition('x', 4);
+
+    assert!(dfa.is_finite(|&v| v));
+
+    // accepts "ab*c"
+    dfa.get_state_mut(2).unwrap().set_transition('b', 2);
+
+    assert!(!dfa.is_finite(|&v| v));
+}
+
+#[test]
+fn test_matcher() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    let mut matcher = dfa.matcher();
+    assert!(!matcher.is_accepting(|&v| v));
+
+    assert!(matcher.feed(&'/'));
+    assert!(!matcher.is_accepting(|&v| v));
+
+    assert!(matcher.feed(&'/'));
+    assert!(matcher.is_accepting(|&v| v));
+
+    assert!(matcher.feed(&' '));
+    assert!(matcher.is_accepting(|&v| v));
+    assert_eq!(matcher.current().unwrap().index(), 2);
+
+    assert!(!matcher.feed(&'/'));
+    assert!(matcher.current().is_none());
+    assert!(!matcher.feed(&' '));
+
+    matcher.reset();
+    assert_eq!(matcher.current().unwrap().index(), 0);
+}
+
+#[test]
+fn test_longest_match() {
+    // accepts "a*"
+    let mut dfa = DFA::new(true);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 0);
+
+    let (len, state) = dfa.longest_match("aaab".chars(), |&v| v).unwrap();
+    assert_eq!(len, 3);
+    assert_eq!(state.index(), 0);
+    assert_eq!(dfa.longest_match("baa".chars(), |&v| v).unwrap().0, 0);
+
+    // accepts "ab" and "abcd"
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(false).set_transition('b', 2);
+    dfa.add_state(true).set_transition('c', 3);
+    dfa.add_state(false).set_transition('d', 4);
+    dfa.add_state(true);
+
+    assert_eq!(dfa.longest_match("abcx".chars(), |&v| v).unwrap().0, 2);
+    assert_eq!(dfa.longest_match("abcde".chars(), |&v| v).unwrap().0, 4);
+    assert!(dfa.longest_match("ax".chars(), |&v| v).is_none());
+}
+
+#[test]
+fn test_minimize() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 2);
+    dfa.add_state(false).set_transition('c', 3);
+    dfa.add_state(false).set_transition('c', 4);
+    dfa.add_state(true);
+    dfa.add_state(true);
+
+    let minimized = dfa.minimize(|&v| v, |values| values[0]);
+
+    assert_eq!(minimized.state_count(), 3);
+    assert!(minimized.traverse("ac".chars()).is_some_and(|x| *x.value()));
+    assert!(minimized.traverse("bc".chars()).is_some_and(|x| *x.value()));
+    assert!(!minimized.traverse("a".chars()).is_some_and(|x| *x.value()));
+    assert!(minimized.traverse("ab".chars()).is_none());
+}
+
+#[test]
+fn test_minimize_dangling_transition() {
+    // "ac" or "bc", where 'x' from the start leads to a state that does not exist
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 2)
+        .set_transition('x', 9);
+    dfa.add_state(false).set_transition('c', 3);
+    dfa.add_state(false)
+        .set_transition('c', 3)
+        .set_transition('x', 7);
+    dfa.add_state(true);
+
+    let minimized = dfa.minimize(|&v| v, |values| values[0]);
+
+    assert_eq!(minimized.state_count(), 3);
+    assert!(minimized.accepts("ac".chars(), |&v| v));
+    assert!(minimized.accepts("bc".chars(), |&v| v));
+    assert!(minimized.traverse("x".chars()).is_none());
+    assert!(minimized.traverse("bx".chars()).is_none());
+}
+
+#[test]
+fn test_minimize_brzozowski() {
+    let mut branches = DFA::new(false);
+    branches
+        .get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 2);
+    branches.add_state(false).set_transition('c', 3);
+    branches.add_state(false).set_transition('c', 4);
+    branches.add_state(true);
+    branches.add_state(true);
+
+    let keywords =
+        DFA::from_keywords(["if", "in", "int", "for", "fun"]).map_values(|v| v.is_some());
+    let ends_in_ab = DFA::from_str_table(
+        "0 -a-> 1\n0 -b-> 0\n1 -a-> 1\n1 -b-> 2\n2 -a-> 1\n2 -b-> 0\n3 -a-> 0\naccept: 2",
+    )
+    .unwrap();
+    let third_from_end = crate::regex::compile("(a|b)*a(a|b)(a|b)")
+        .unwrap()
+        .into_nfa()
+        .unwrap()
+        .into_dfa()
+        .map_values(|(finish, _)| finish);
+    let empty = DFA::from_str_table("0 -a-> 1\n1 -a-> 0").unwrap();
+
+    for dfa in [branches, keywords, ends_in_ab, third_from_end, empty] {
+        let hopcroft = dfa.clone().minimize(|&v| v, |values| values[0]);
+        let brzozowski = dfa.clone().minimize_brzozowski(|&v| v);
+
+        assert_eq!(hopcroft.state_count(), brzozowski.state_count());
+        assert!(hopcroft.is_isomorphic(&brzozowski));
+        assert!(dfa
+            .symmetric_difference(&brzozowski, |&v| v, |&v| v)
+            .is_empty(|&v| v));
+    }
+}
+
+#[test]
+fn test_canonicalize() {
+    // both accept "ab" and "ac", built in different orders and with a redundant state
+    let mut first = DFA::new(false);
+    first.get_state_mut(0).unwrap().set_transition('a', 1);
+    first
+        .add_state(false)
+        .set_transition('b', 2)
+        .set_transition('c', 3);
+    first.add_state(true);
+    first.add_state(true);
+
+    let mut second = DFA::new(true);
+    second.add_state(false).set_transition('a', 2);
+    second
+        .add_state(false)
+        .set_transition('c', 0)
+        .set_transition('b', 0);
+    second.start = 1;
+
+    let mut first = first.minimize(|&v| v, |values| values[0]);
+    let mut second = second.minimize(|&v| v, |values| values[0]);
+    first.canonicalize();
+    let reassignments = second.canonicalize();
+
+    assert_eq!(reassignments, vec![0, 1, 2]);
+    assert_eq!(first, second);
+    assert_eq!(first.to_string(), second.to_string());
+
+    // unreachable states move to the end
+    let mut dfa = DFA::new("start");
+    dfa.add_state("orphan");
+    dfa.add_state("end");
+    dfa.get_state_mut(0).unwrap().set_transition('z', 2);
+    assert_eq!(dfa.canonicalize(), vec![0, 2, 1]);
+    assert_eq!(*dfa.traverse("z".chars()).unwrap().value(), "end");
+}
+
+#[test]
+fn test_is_isomorphic() {
+    let mut dfa = DFA::new(false);
+    dfa.add_state(true).set_transition('b', 0);
+    dfa.add_state(false).set_transition('a', 1);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 2)
+        .set_transition('b', 1);
+
+    let mut canonical = dfa.clone();
+    canonical.canonicalize();
+    assert_ne!(canonical, dfa);
+    assert!(dfa.is_isomorphic(&canonical));
+    assert!(dfa.is_isomorphic_by(&canonical, |a, b| a == b));
+    assert!(!dfa.is_isomorphic_by(&canonical.clone().map_values(|v| !v), |a, b| a == b));
+
+    // same shape except for one transition
+    let mut different = canonical.clone();
+    different.get_state_mut(2).unwrap().set_transition('a', 0);
+    assert!(!dfa.is_isomorphic(&different));
+    assert!(!different.is_isomorphic(&dfa));
+}
+
+#[test]
+fn test_intersect() {
+    // strings ending in 'a'
+    let mut ends_in_a = DFA::new(false);
+    ends_in_a
+        .get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 0);
+    ends_in_a
+        .add_state(true)
+        .set_transition('a', 1)
+        .set_transition('b', 0);
+
+    // strings of even length
+    let mut even = DFA::new(true);
+    even.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 1);
+    even.add_state(false)
+        .set_transition('a', 0)
+        .set_transition('b', 0);
+
+    let both = ends_in_a.intersect(&even);
+    let accepts = |input: &str| both.accepts(input.chars(), |&(x, y)| x && y);
+
+    assert!(accepts("ba"));
+    assert!(accepts("abba"));
+    assert!(!accepts("a"));
+    assert!(!accepts("ab"));
+    assert!(!accepts(""));
+}
+
+#[test]
+fn test_products_dangling_transition() {
+    // "ab", with a dangling transition on 'b' from the start
+    let mut ab = DFA::from_str_table("0 -a-> 1\n1 -b-> 2\naccept: 2").unwrap();
+    ab.get_state_mut(0).unwrap().set_transition('b', 9);
+    // "a" or "b", with a dangling transition on 'b' after "a"
+    let mut one = DFA::from_str_table("0 -a-> 1\n0 -b-> 1\naccept: 1").unwrap();
+    one.get_state_mut(1).unwrap().set_transition('b', 7);
+
+    let both = ab.intersect(&one);
+    assert!(both.is_empty(|&(x, y)| x && y));
+    assert!(both.traverse("ab".chars()).is_none());
+
+    let either = ab.union(&one);
+    let accepts =
+        |input: &str| either.accepts(input.chars(), |(x, y)| *x == Some(true) || *y == Some(true));
+    assert!(accepts("ab"));
+    assert!(accepts("a"));
+    assert!(accepts("b"));
+    assert!(!accepts("bb"));
+
+    let difference = ab.difference(&one, |&v| v, |&v| v);
+    assert!(difference.accepts("ab".chars(), |&v| v));
+    assert!(!difference.accepts("b".chars(), |&v| v));
+
+    let symmetric = ab.symmetric_difference(&one, |&v| v, |&v| v);
+    assert!(symmetric.accepts("b".chars(), |&v| v));
+    assert!(!symmetric.accepts("bb".chars(), |&v| v));
+
+    assert_eq!(ab.is_subset_of(&one, |&v| v, |&v| v), Err(vec!['a', 'b']));
+    assert!(DFA::literal("a".chars())
+        .is_subset_of(&one, |&v| v, |&v| v)
+        .is_ok());
+
+    let dot = ab.diff_dot(&one, |&v| v, |&v| v);
+    assert!(dot.contains("fillcolor=red"));
+    assert!(dot.contains("fillcolor=blue"));
+}
+
+#[test]
+fn test_union() {
+    // strings over {'a', 'x'} ending in 'a'
+    let mut ends_in_a = DFA::new(false);
+    ends_in_a
+        .get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('x', 0);
+    ends_in_a
+        .add_state(true)
+        .set_transition('a', 1)
+        .set_transition('x', 0);
+
+    // strings over {'b', 'y'} ending in 'b'
+    let mut ends_in_b = DFA::new(false);
+    ends_in_b
+        .get_state_mut(0)
+        .unwrap()
+        .set_transition('b', 1)
+        .set_transition('y', 0);
+    ends_in_b
+        .add_state(true)
+        .set_transition('b', 1)
+        .set_transition('y', 0);
+
+    let either = ends_in_a.union(&ends_in_b);
+    let accepts = |input: &str| {
+        either.accepts(input.chars(), |(x, y)| {
+            x.unwrap_or_default() || y.unwrap_or_default()
+        })
+    };
+
+    assert!(accepts("xa"));
+    assert!(accepts("yb"));
+    assert!(accepts("a"));
+    assert!(!accepts("xc"));
+    assert!(!accepts("ax"));
+    assert!(!accepts(""));
+}
+
+#[test]
+fn test_complete() {
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(false).set_transition('b', 2);
+    dfa.add_state(true);
+
+    let dfa = dfa.complete(false);
+    assert_eq!(dfa.state_count(), 4);
+
+    for input in ["", "a", "b", "ab", "ba", "abab", "bbba"] {
+        assert!(dfa.traverse(input.chars()).is_some());
+    }
+    assert!(dfa.accepts("ab".chars(), |&v| v));
+    assert!(!dfa.accepts("aba".chars(), |&v| v));
+
+    let dfa = dfa.complete(false);
+    assert_eq!(dfa.state_count(), 4);
+
+    // dangling transitions are missing ones, including one that points where the trap goes
+    let mut dangling = DFA::new(false);
+    dangling
+        .get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 9);
+    dangling.add_state(true).set_transition('a', 2);
+    assert!(!dangling.stats(|&v| v).is_complete);
+
+    let dangling = dangling.complete(false);
+    assert_eq!(dangling.state_count(), 3);
+    assert!(dangling.stats(|&v| v).is_complete);
+    for input in ["b", "bb", "ab", "aa", "aab"] {
+        assert_eq!(
+            dangling.traverse(input.chars()).map(|st| st.index()),
+            Some(2)
+        );
+    }
+    assert!(dangling.accepts("a".chars(), |&v| v));
+}
+
+#[test]
+fn test_remove_dead_states() {
+    // accepts "ab", with a sink trap on every other input
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 3);
+    dfa.add_state(false)
+        .set_transition('a', 3)
+        .set_transition('b', 2);
+    dfa.add_state(true)
+        .set_transition('a', 3)
+        .set_transition('b', 3);
+    dfa.add_state(false)
+        .set_transition('a', 3)
+        .set_transition('b', 3);
+
+    let inputs = ["", "a", "b", "ab", "ba", "aba", "abb"];
+    let before: Vec<bool> = inputs
+        .iter()
+        .map(|input| dfa.accepts(input.chars(), |&v| v))
+        .collect();
+
+    dfa.remove_dead_states(|&v| v);
+    assert_eq!(dfa.state_count(), 3);
+
+    let after: Vec<bool> = inputs
+        .iter()
+        .map(|input| dfa.accepts(input.chars(), |&v| v))
+        .collect();
+    assert_eq!(before, after);
+
+    // the start state survives even when it is dead
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(false).set_transition('a', 0);
+    dfa.remove_dead_states(|&v| v);
+    assert_eq!(dfa.state_count(), 1);
+    assert!(dfa.traverse("a".chars()).is_none());
+}
+
+#[test]
+fn test_remove_unreachable_states() {
+    let mut dfa = DFA::new("start");
+    dfa.get_state_mut(0).unwrap().set_transition('a', 2);
+    // an island of two states pointing at each other and into the reachable part
+    dfa.add_state("island a")
+        .set_transition('a', 3)
+        .set_transition('b', 2);
+    dfa.add_state("end");
+    dfa.add_state("island b").set_transition('a', 1);
+
+    assert_eq!(dfa.reachable_states(), HashSet::from([0, 2]));
+
+    let reassignments = dfa.remove_unreachable_states();
+    assert_eq!(reassignments, vec![Some(0), None, Some(1), None]);
+    assert_eq!(dfa.state_count(), 2);
+    assert_eq!(*dfa.traverse("a".chars()).unwrap().value(), "end");
+}
+
+#[test]
+fn test_difference() {
+    // a*b and ab
+    let many = DFA::from_str_table("0 -a-> 0\n0 -b-> 1\naccept: 1").unwrap();
+    let one = DFA::from_str_table("0 -a-> 1\n1 -b-> 2\naccept: 2").unwrap();
+
+    let difference = many.difference(&one, |&v| v, |&v| v);
+    for (input, expected) in [("b", true), ("ab", false), ("aab", true), ("a", false)] {
+        assert_eq!(
+            difference.accepts(input.chars(), |&v| v),
+            expected,
+            "{}",
+            input
+        );
+    }
+    assert!(one.difference(&many, |&v| v, |&v| v).is_empty(|&v| v));
+
+    let symmetric = many.symmetric_difference(&one, |&v| v, |&v| v);
+    assert!(symmetric.accepts("aab".chars(), |&v| v));
+    assert!(!symmetric.accepts("ab".chars(), |&v| v));
+    assert_eq!(symmetric.shortest_accepted(|&v| v), Some(vec!['b']));
+
+    assert!(many
+        .symmetric_difference(&many, |&v| v, |&v| v)
+        .is_empty(|&v| v));
+    assert!(many
+        .symmetric_difference(&many.clone().minimize(|&v| v, |v| v[0]), |&v| v, |&v| v)
+        .is_empty(|&v| v));
+}
+
+#[test]
+fn test_missing_transitions() {
+    let dfa = DFA::universal(['a', 'b']);
+    assert!(dfa.missing_transitions().is_empty());
+
+    // the alphabet is {a, c}, which 0 has both of and 1 has neither of
+    let dfa = DFA::from_str_table("0 -a-> 0\n0 -c-> 1\naccept: 1").unwrap();
+    let mut missing = dfa.missing_transitions();
+    missing.sort_unstable();
+    assert_eq!(missing, vec![(1, 'a'), (1, 'c')]);
+
+    let partial = DFA::from_str_table("0 -a-> 1\n1 -b-> 0\naccept: 1").unwrap();
+    let mut missing = partial.missing_transitions();
+    missing.sort_unstable();
+    assert_eq!(missing, vec![(0, 'b'), (1, 'a')]);
+    assert!(partial.complete(false).missing_transitions().is_empty());
+
+    // a dangling transition is a gap like a missing one
+    let mut dangling = DFA::universal(['a', 'b']);
+    dangling.get_state_mut(0).unwrap().set_transition('b', 5);
+    assert_eq!(dangling.missing_transitions(), vec![(0, 'b')]);
+}
+
+#[test]
+fn test_diff_dot() {
+    // a*b and ab
+    let many = DFA::from_str_table("0 -a-> 0\n0 -b-> 1\naccept: 1").unwrap();
+    let one = DFA::from_str_table("0 -a-> 1\n1 -b-> 2\naccept: 2").unwrap();
+
+    let dot = many.diff_dot(&one, |&v| v, |&v| v);
+    assert!(dot.starts_with("digraph {"));
+    // "b" and "aab" are only accepted by `many`
+    assert!(dot.contains("fillcolor=red"));
+    assert!(!dot.contains("fillcolor=blue"));
+    assert!(dot.contains("shape=doublecircle];"));
+
+    let dot = one.diff_dot(&many, |&v| v, |&v| v);
+    assert!(dot.contains("fillcolor=blue"));
+
+    let minimal = many.clone().minimize(|&v| v, |v| v[0]);
+    assert!(!many.diff_dot(&minimal, |&v| v, |&v| v).contains("filled"));
+}
+
+#[test]
+fn test_is_subset_of() {
+    // a(b|c) and a(b|c)*
+    let tight = DFA::from_str_table("0 -a-> 1\n1 -b-> 2\n1 -c-> 2\naccept: 2").unwrap();
+    let loose = DFA::from_str_table("0 -a-> 1\n1 -b-> 1\n1 -c-> 1\naccept: 1").unwrap();
+
+    assert_eq!(tight.is_subset_of(&loose, |&v| v, |&v| v), Ok(()));
+    assert_eq!(loose.is_subset_of(&tight, |&v| v, |&v| v), Err(vec!['a']));
+    assert_eq!(tight.is_subset_of(&tight, |&v| v, |&v| v), Ok(()));
+
+    let witness = loose.is_subset_of(&tight, |&v| v, |&v| v).unwrap_err();
+    assert!(loose.accepts(witness.iter(), |&v| v));
+    assert!(!tight.accepts(witness.iter(), |&v| v));
+}
+
+#[test]
+fn test_complement() {
+    // accepts "ab", with a dead end on "ac"
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0).unwrap().set_transition('a', 1);
+    dfa.add_state(false)
+        .set_transition('b', 2)
+        .set_transition('c', 3);
+    dfa.add_state(true);
+    dfa.add_state(false);
+
+    let complement = dfa.complement(|&v| v);
+
+    assert_eq!(complement.state_count(), 5);
+    assert!(!complement.accepts("ab".chars(), |&v| v));
+    assert!(complement.accepts("ac".chars(), |&v| v));
+    assert!(complement.accepts("".chars(), |&v| v));
+    assert!(complement.accepts("b".chars(), |&v| v));
+    assert!(complement.accepts("abcab".chars(), |&v| v));
+
+    // a dangling transition rejects, so its strings are accepted by the complement
+    let mut dangling = DFA::new(false);
+    dangling
+        .get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 9);
+    dangling.add_state(true);
+    assert!(!dangling.accepts("b".chars(), |&v| v));
+
+    let complement = dangling.complement(|&v| v);
+    assert!(complement.accepts("b".chars(), |&v| v));
+    assert!(complement.accepts("bb".chars(), |&v| v));
+    assert!(!complement.accepts("a".chars(), |&v| v));
+    assert!(complement.accepts("ab".chars(), |&v| v));
+}
+
+#[test]
+fn test_complement_empty_alphabet() {
+    let dfa: DFA<char, bool> = DFA::new(true);
+
+    let complement = dfa.complement(|&v| v);
+
+    assert_eq!(complement.state_count(), 1);
+    assert!(!complement.accepts("".chars(), |&v| v));
+}
+
+#[test]
+fn test_shortest_accepted() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('x', 0)
+        .set_transition('a', 1);
+    dfa.add_state(false)
+        .set_transition('b', 2)
+        .set_transition('x', 0);
+    dfa.add_state(false).set_transition('c', 3);
+    dfa.add_state(true).set_transition('a', 3);
+    dfa.add_state(true);
+
+    assert_eq!(dfa.shortest_accepted(|&v| v), Some(vec!['a', 'b', 'c']));
+    assert_eq!(dfa.shortest_accepted(|_| false), None);
+    assert_eq!(dfa.shortest_accepted(|_| true), Some(vec![]));
+}
+
+#[test]
+fn test_pumping_witness() {
+    // a*b
+    let dfa = DFA::from_str_table("0 -a-> 0\n0 -b-> 1\naccept: 1").unwrap();
+    let (x, y, z) = dfa.pumping_witness(|&v| v).unwrap();
+    assert_eq!(
+        (x.as_slice(), y.as_slice(), z.as_slice()),
+        (&[][..], &['a'][..], &['b'][..])
+    );
+
+    // c(ab)*d, where the loop is only reached after a prefix, plus an unusable loop on 'e'
+    let dfa = DFA::from_str_table(
+        "0 -c-> 1\n1 -a-> 2\n2 -b-> 1\n1 -d-> 3\n0 -e-> 4\n4 -e-> 4\naccept: 3",
+    )
+    .unwrap();
+    let (x, y, z) = dfa.pumping_witness(|&v| v).unwrap();
+    assert_eq!(
+        (x, y.clone(), z.clone()),
+        (vec!['c'], vec!['a', 'b'], vec!['d'])
+    );
+    for n in 0..4 {
+        let mut input = vec!['c'];
+        for _ in 0..n {
+            input.extend(y.iter());
+        }
+        input.extend(z.iter());
+        assert!(dfa.accepts(input.iter(), |&v| v));
+    }
+
+    let finite = DFA::from_keywords(["if", "in", "int"]);
+    assert_eq!(finite.pumping_witness(Option::is_some), None);
+}
+
+#[test]
+fn test_shortest_path() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false)
+        .set_transition('/', 2)
+        .set_transition('x', 9);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    assert_eq!(dfa.shortest_path(0, 2), Some(vec!['/', '/']));
+    assert_eq!(dfa.shortest_path(1, 1), Some(vec![]));
+    assert_eq!(dfa.shortest_path(2, 0), None);
+    assert_eq!(dfa.shortest_path(0, 9), None);
+    assert_eq!(dfa.shortest_path(9, 0), None);
+}
+
+#[test]
+fn test_accepted_up_to() {
+    // accepts "a+", with a dead end on 'b'
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 1)
+        .set_transition('b', 2);
+    dfa.add_state(true).set_transition('a', 1);
+    dfa.add_state(false).set_transition('b', 2);
+
+    let accepted: Vec<String> = dfa
+        .accepted_up_to(3, |&v| v)
+        .map(|s| s.into_iter().collect())
+        .collect();
+
+    assert_eq!(accepted, vec!["a", "aa", "aaa"]);
+    assert_eq!(dfa.accepted_up_to(0, |&v| v).count(), 0);
+    assert_eq!(dfa.accepted_up_to(2, |&v| !v).count(), 3);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_dfa_serde_round_trip() {
+    let mut dfa = DFA::from_str_table("0 -a-> 1\n1 -b-> 0\naccept: 1").unwrap();
+    dfa.get_state_mut(1).unwrap().set_transition('x', 5);
+
+    let json = serde_json::to_string(&dfa).unwrap();
+    let restored: DFA<char, bool> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, dfa);
+
+    for invalid in [
+        r#"{"values":[true],"transitions":[{}],"start":3}"#,
+        r#"{"values":[true],"transitions":[],"start":0}"#,
+        r#"{"values":[true],"transitions":[{},{}],"start":0}"#,
+        r#"{"values":[],"transitions":[],"start":0}"#,
+    ] {
+        assert!(
+            serde_json::from_str::<DFA<char, bool>>(invalid).is_err(),
+            "{}",
+            invalid
+        );
+    }
+    let err = serde_json::from_str::<DFA<char, bool>>(
+        r#"{"values":[true],"transitions":[{}],"start":3}"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("start index 3 must be valid"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_flat_round_trip() {
+    let dfa = DFA::from_str_table(
+        "start: 0
+         accept: 2
+         0 -a-> 1
+         1 -b-> 2
+         2 -a-> 1",
+    )
+    .unwrap();
+
+    let flat = dfa.to_flat(|&accepting| accepting);
+    assert_eq!(flat.start, 0);
+    assert_eq!(flat.accepting, vec![false, false, true]);
+    assert_eq!(flat.edges.len(), 3);
+
+    let json = serde_json::to_string(&flat).unwrap();
+    let flat: FlatDFA<char> = serde_json::from_str(&json).unwrap();
+    let restored = DFA::from_flat(flat).unwrap();
+    assert_eq!(restored, dfa);
+    for input in ["", "a", "ab", "aba", "abab", "b"] {
+        assert_eq!(
+            restored.accepts(input.chars(), |&accepting| accepting),
+            dfa.accepts(input.chars(), |&accepting| accepting),
+        );
+    }
+
+    let dangling = FlatDFA {
+        start: 0,
+        accepting: vec![true],
+        edges: vec![(0, 'a', 4)],
+    };
+    assert_eq!(
+        DFA::from_flat(dangling),
+        Err(BuildError::InvalidTransitionTarget(4))
+    );
+}
+
+#[cfg(all(feature = "rand", feature = "std"))]
+#[test]
+fn test_sample_accepted() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // strings over {a, b} with an odd number of b's
+    let mut dfa = DFA::new(false);
+    dfa.get_state_mut(0)
+        .unwrap()
+        .set_transition('a', 0)
+        .set_transition('b', 1);
+    dfa.add_state(true)
+        .set_transition('a', 1)
+        .set_transition('b', 0);
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut seen = HashSet::new();
+    for _ in 0..200 {
+        let sampled = dfa.sample_accepted(4, |&v| v, &mut rng).unwrap();
+        assert_eq!(sampled.len(), 4);
+        assert!(dfa.accepts(sampled.iter(), |&v| v));
+        seen.insert(sampled);
+    }
+    // all 8 accepted strings of length 4 turn up
+    assert_eq!(seen.len(), 8);
+
+    assert_eq!(dfa.sample_accepted(0, |&v| v, &mut rng), None);
+    assert_eq!(dfa.sample_accepted(1, |&v| v, &mut rng), Some(vec!['b']));
+}
+
+#[test]
+fn test_to_dot() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    let dot = dfa.to_dot(|&v| v);
+
+    assert!(dot.starts_with("digraph {"));
+    assert!(dot.contains("start -> 0;"));
+    assert!(dot.contains("2 [label=\"2: true\", shape=doublecircle];"));
+    assert!(dot.contains("0 -> 1 [label=\"/\"];"));
+    // one edge per transition, plus the start arrow
+    assert_eq!(dot.matches(" -> ").count(), 4);
+
+    // a dangling transition draws no edge to a phantom state
+    dfa.get_state_mut(1).unwrap().set_transition('x', 9);
+    let dot = dfa.to_dot(|&v| v);
+    assert!(!dot.contains('9'));
+    assert_eq!(dot.matches(" -> ").count(), 4);
+}
+
+#[test]
+fn test_to_mermaid() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('/', 1);
+    dfa.add_state(false).set_transition('/', 2);
+    dfa.add_state(true).set_transition(' ', 2);
+
+    let mermaid = dfa.to_mermaid(|&v| v);
+
+    assert!(mermaid.starts_with("stateDiagram-v2\n"));
+    assert!(mermaid.contains("[*] --> S0\n"));
+    assert!(mermaid.contains("S0 --> S1: /\n"));
+    assert!(mermaid.contains("S2 --> S2:  \n"));
+    assert!(mermaid.contains("S2 --> [*]\n"));
+    // one line per transition and accepting state, plus the start arrow
+    assert_eq!(mermaid.matches(" --> ").count(), 5);
+
+    // a dangling transition draws no edge to a phantom state
+    dfa.get_state_mut(1).unwrap().set_transition('x', 9);
+    let mermaid = dfa.to_mermaid(|&v| v);
+    assert!(!mermaid.contains("S9"));
+    assert_eq!(mermaid.matches(" --> ").count(), 5);
+}
+
+#[test]
+fn test_to_dot_deterministic() {
+    // each build gets its own hasher seed, and so its own transition order
+    let build = || {
+        let mut dfa = DFA::new(false);
+        dfa.add_state(true);
+        for c in 'a'..='z' {
+            dfa.get_state_mut(0)
+                .unwrap()
+                .set_transition(c, (c as usize) % 2);
+        }
+        dfa
+    };
+
+    let dot = build().to_dot(|&v| v);
+    for _ in 0..10 {
+        assert_eq!(build().to_dot(|&v| v), dot);
+    }
+    assert!(dot.find("0 -> 1 [label=\"a\"]") < dot.find("0 -> 0 [label=\"b\"]"));
+}
+
+#[test]
+fn test_next_dangling_transition() {
+    let mut dfa = DFA::new(false);
+
+    dfa.get_state_mut(0).unwrap().set_transition('a', 5);
+
+    assert!(dfa.get_start().next(&'a').is_none());
+    assert!(dfa.traverse("a".chars()).is_none());
+}
@@ -0,0 +1,68 @@
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Write};
+
+/// Incrementally writes a directed graph in the DOT language.
+pub(crate) struct DotWriter {
+    out: String,
+}
+
+impl DotWriter {
+    /// Starts a new graph whose start states are marked by arrows from an invisible node.
+    pub(crate) fn new(starts: impl IntoIterator<Item = usize>) -> Self {
+        let mut out = String::from("digraph {\n");
+        out.push_str("    start [shape=point, style=invis];\n");
+        for start in starts {
+            writeln!(out, "    start -> {start};").unwrap();
+        }
+        DotWriter { out }
+    }
+
+    /// Adds a state, drawn as a double circle if it is accepting.
+    pub(crate) fn node(&mut self, index: usize, label: impl Display, accepting: bool) {
+        let shape = if accepting { "doublecircle" } else { "circle" };
+        writeln!(
+            self.out,
+            "    {index} [label=\"{}\", shape={shape}];",
+            escape(label)
+        )
+        .unwrap();
+    }
+
+    /// Adds a state like [`DotWriter::node`], filled with the given color.
+    pub(crate) fn filled_node(
+        &mut self,
+        index: usize,
+        label: impl Display,
+        accepting: bool,
+        color: &str,
+    ) {
+        let shape = if accepting { "doublecircle" } else { "circle" };
+        writeln!(
+            self.out,
+            "    {index} [label=\"{}\", shape={shape}, style=filled, fillcolor={color}];",
+            escape(label)
+        )
+        .unwrap();
+    }
+
+    /// Adds a labeled transition, drawn dashed if requested.
+    pub(crate) fn edge(&mut self, from: usize, to: usize, label: impl Display, dashed: bool) {
+        let style = if dashed { ", style=dashed" } else { "" };
+        writeln!(
+            self.out,
+            "    {from} -> {to} [label=\"{}\"{style}];",
+            escape(label)
+        )
+        .unwrap();
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+/// Escapes a value for use inside a quoted DOT string.
+fn escape(value: impl Display) -> String {
+    value.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,122 @@
+use alloc::{vec, vec::Vec};
+
+/// Finds the strongly connected components of a directed graph with Tarjan's algorithm.
+///
+/// `adjacency[v]` lists the successors of `v`; successors without an entry are ignored.
+/// Every vertex is in exactly one component, each sorted by index, and a component comes
+/// after every component reachable from it.
+pub(crate) fn sccs(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let n = adjacency.len();
+    let mut index = vec![UNVISITED; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        // each frame is a vertex and how many of its successors have been looked at
+        let mut calls = vec![(root, 0)];
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((v, seen)) = calls.last_mut() {
+            let v = *v;
+            if let Some(&w) = adjacency[v].get(*seen) {
+                *seen += 1;
+                if w >= n {
+                    continue;
+                }
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    lowlink[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+
+            if lowlink[v] == index[v] {
+                let mut component = Vec::new();
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Orders the vertices of a directed graph so that every edge goes from an earlier vertex to
+/// a later one, or returns a vertex on a cycle if there is no such order.
+///
+/// `adjacency[v]` lists the successors of `v`; successors without an entry are ignored.
+pub(crate) fn topo_order(adjacency: &[Vec<usize>]) -> Result<Vec<usize>, usize> {
+    const UNVISITED: u8 = 0;
+    const ON_PATH: u8 = 1;
+    const FINISHED: u8 = 2;
+
+    let n = adjacency.len();
+    let mut marks = vec![UNVISITED; n];
+    let mut order = Vec::with_capacity(n);
+
+    for root in 0..n {
+        if marks[root] != UNVISITED {
+            continue;
+        }
+
+        // each frame is a vertex and how many of its successors have been looked at
+        let mut calls = vec![(root, 0)];
+        marks[root] = ON_PATH;
+
+        while let Some((v, seen)) = calls.last_mut() {
+            let v = *v;
+            if let Some(&w) = adjacency[v].get(*seen) {
+                *seen += 1;
+                match marks.get(w) {
+                    Some(&ON_PATH) => return Err(w),
+                    Some(&UNVISITED) => {
+                        marks[w] = ON_PATH;
+                        calls.push((w, 0));
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            calls.pop();
+            marks[v] = FINISHED;
+            order.push(v);
+        }
+    }
+
+    // every vertex finishes after all of its successors
+    order.reverse();
+    Ok(order)
+}
@@ -0,0 +1,150 @@
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::hash::Hash;
+
+use crate::{collections::HashMap, nfa::NFA, state::State, transition::RealTransition};
+
+/// Runs an NFA as a DFA built on demand. Each DFA state is a set of NFA states, and is only
+/// computed the first time a symbol leads to it, so memory grows with the part of the DFA that
+/// inputs actually use instead of with the whole powerset construction.
+pub struct LazyDFA<'nfa, L, S> {
+    nfa: &'nfa NFA<L, S>,
+    sets: Vec<BTreeSet<usize>>,
+    indices: HashMap<BTreeSet<usize>, usize>,
+    /// The cached transitions of each DFA state, where `None` means the set would be empty.
+    transitions: Vec<HashMap<L, Option<usize>>>,
+    current: Option<usize>,
+}
+
+impl<'nfa, L, S> LazyDFA<'nfa, L, S>
+where
+    L: Clone + Eq + Hash,
+{
+    /// Starts running `nfa` from the set of its start states.
+    pub fn new(nfa: &'nfa NFA<L, S>) -> Self {
+        let start: BTreeSet<usize> = nfa.starts().iter().copied().collect();
+        LazyDFA {
+            nfa,
+            indices: HashMap::from([(start.clone(), 0)]),
+            sets: vec![start],
+            transitions: vec![HashMap::new()],
+            current: Some(0),
+        }
+    }
+
+    /// Follows the transition for the given symbol, computing it first if it is not cached.
+    /// Returns false if no NFA state is left, after which this stays stuck until reset.
+    pub fn step(&mut self, symbol: &L) -> bool {
+        let Some(current) = self.current else {
+            return false;
+        };
+
+        self.current = match self.transitions[current].get(symbol) {
+            Some(&dest) => dest,
+            None => {
+                let next = self.nfa.step_set(&self.sets[current], symbol);
+                let dest = (!next.is_empty()).then(|| {
+                    *self.indices.entry(next).or_insert_with_key(|next| {
+                        self.sets.push(next.clone());
+                        self.transitions.push(HashMap::new());
+                        self.sets.len() - 1
+                    })
+                });
+                self.transitions[current].insert(symbol.clone(), dest);
+                dest
+            }
+        };
+
+        self.current.is_some()
+    }
+
+    /// Resets, then steps through every symbol, returning whether an NFA finish state is
+    /// reached at the end.
+    pub fn accepts<'a, I>(&mut self, symbols: I) -> bool
+    where
+        I: IntoIterator<Item = &'a L>,
+        L: 'a,
+    {
+        self.reset();
+        symbols.into_iter().all(|symbol| self.step(symbol)) && self.is_accepting()
+    }
+}
+
+impl<'nfa, L, S> LazyDFA<'nfa, L, S> {
+    /// Gets the NFA states making up the current DFA state, or `None` if an earlier symbol left
+    /// no NFA state.
+    pub fn current(&self) -> Option<&BTreeSet<usize>> {
+        self.current.map(|i| &self.sets[i])
+    }
+
+    /// Gets the NFA states making up the current DFA state, or an empty list if an earlier
+    /// symbol left no NFA state.
+    pub fn current_states(&self) -> Vec<&'nfa State<S, RealTransition<L>>> {
+        let nfa = self.nfa;
+        self.current()
+            .into_iter()
+            .flatten()
+            .filter_map(|&s| nfa.get_state(s))
+            .collect()
+    }
+
+    /// Returns whether some NFA state in the current DFA state is a finish state.
+    pub fn is_accepting(&self) -> bool {
+        self.current_states().iter().any(|st| st.is_finish())
+    }
+
+    /// Moves back to the start state, keeping every DFA state computed so far.
+    pub fn reset(&mut self) {
+        self.current = Some(0);
+    }
+
+    /// Gets how many DFA states have been computed so far.
+    pub fn cached_states(&self) -> usize {
+        self.sets.len()
+    }
+}
+
+#[test]
+fn test_lazy_dfa() {
+    use crate::nfa::NFABuilder;
+
+    // the strings over {a, b} whose 12th symbol from the end is an 'a', whose full DFA has
+    // 2^12 states
+    let n = 12;
+    let mut builder = NFABuilder::default();
+    let mut start = State::new(false, ());
+    start
+        .add_transition(RealTransition::new('a', 0))
+        .add_transition(RealTransition::new('b', 0))
+        .add_transition(RealTransition::new('a', 1));
+    builder.add_state(start).set_start(0);
+    for i in 1..n {
+        let mut state = State::new(false, ());
+        state
+            .add_transition(RealTransition::new('a', i + 1))
+            .add_transition(RealTransition::new('b', i + 1));
+        builder.add_state(state);
+    }
+    builder.add_state(State::new(true, ()));
+    let nfa = builder.build().unwrap();
+
+    let mut lazy = LazyDFA::new(&nfa);
+    let input: Vec<char> = "abbbbbbbbbbbb".chars().collect();
+    assert!(!lazy.accepts(input.iter()));
+    assert!(lazy.accepts(input[..12].iter()));
+    assert!(!lazy.accepts(input[1..].iter()));
+
+    // each step visits at most one new state, far fewer than the full construction
+    assert!(lazy.cached_states() <= input.len() + 1);
+    assert_eq!(nfa.clone().into_dfa().state_count(), 1 << n);
+
+    // revisiting cached states computes nothing new
+    let cached = lazy.cached_states();
+    assert!(!lazy.accepts(input.iter()));
+    assert_eq!(lazy.cached_states(), cached);
+
+    assert!(!lazy.step(&'c'));
+    assert!(lazy.current().is_none());
+    assert!(!lazy.step(&'a'));
+    lazy.reset();
+    assert_eq!(lazy.current(), Some(&BTreeSet::from([0])));
+}
@@ -1,6 +1,30 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(dead_code)]
 
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+}
+
+pub mod bitset_nfa;
+pub mod byte_dfa;
+mod collections;
+pub mod dfa;
+mod dot;
+mod graph;
+pub mod lazy_dfa;
+pub mod multi_matcher;
 pub mod nfa;
 pub mod nfae;
+pub mod predicate_nfa;
+pub mod regex;
 pub mod state;
+pub mod stats;
+pub mod transducer;
 pub mod transition;
+pub mod weighted_dfa;
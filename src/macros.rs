@@ -0,0 +1,154 @@
+/// Builds a DFA from a list of states and edges.
+///
+/// The states must be numbered in order from 0, each mapped to its value, and every edge
+/// goes from one state to another on a literal symbol. An edge or start state that refers to
+/// an undefined state is a compile error. Conflicting edges panic when the DFA is built.
+///
+/// ```
+/// let dfa = fsm::dfa! {
+///     start: 0;
+///     states: { 0 => false, 1 => true };
+///     edges: { 0 -'a'-> 1, 1 -'a'-> 1 }
+/// };
+/// assert!(dfa.accepts("aa".chars(), |&v| v));
+/// ```
+///
+/// ```compile_fail
+/// let dfa = fsm::dfa! {
+///     start: 0;
+///     states: { 0 => false, 1 => true };
+///     edges: { 0 -'a'-> 2 }
+/// };
+/// ```
+#[macro_export]
+macro_rules! dfa {
+    (
+        start: $start:literal;
+        states: { $($index:literal => $value:expr),+ $(,)? };
+        edges: { $($from:literal -$symbol:literal-> $to:literal),* $(,)? } $(;)?
+    ) => {{
+        $crate::__check_states!($start; $($index),+; $($from, $to),*);
+        $crate::dfa::DFA::from_parts(
+            $crate::__private::vec![$($value),+],
+            $crate::__private::vec![$(($from, $symbol, $to)),*],
+            $start,
+        )
+        .unwrap()
+    }};
+}
+
+/// Builds an NFA from a list of states and edges.
+///
+/// The states must be numbered in order from 0, each mapped to whether it is a finish state,
+/// optionally followed by `;` and its data, which is `()` otherwise. There may be several start
+/// states, and an edge or start state that refers to an undefined state is a compile error.
+/// Building panics if no state is a finish.
+///
+/// ```
+/// let nfa = fsm::nfa! {
+///     start: 0;
+///     states: { 0 => false, 1 => true };
+///     edges: { 0 -'a'-> 0, 0 -'a'-> 1 }
+/// };
+/// assert!(nfa.accepts(['a', 'a'].iter()));
+/// ```
+#[macro_export]
+macro_rules! nfa {
+    (
+        start: $($start:literal),+;
+        states: { $($index:literal => $finish:expr $(; $data:expr)?),+ $(,)? };
+        edges: { $($from:literal -$symbol:literal-> $to:literal),* $(,)? } $(;)?
+    ) => {{
+        $crate::__check_states!($($start),+; $($index),+; $($from, $to),*);
+        let mut states = $crate::__private::vec![
+            $($crate::state::State::new($finish, $crate::__state_data!($($data)?))),+
+        ];
+        $(
+            states[$from].add_transition($crate::transition::RealTransition::new($symbol, $to));
+        )*
+        let mut builder = $crate::nfa::NFABuilder::default();
+        for state in states {
+            builder.add_state(state);
+        }
+        $(
+            builder.add_start($start);
+        )+
+        builder.build().unwrap()
+    }};
+}
+
+/// Fails to compile unless the states are numbered in order from 0 and every start and edge
+/// endpoint is one of them.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_states {
+    ($($start:literal),+; $($index:literal),+; $($endpoint:literal),*) => {
+        const _: () = {
+            let states: &[usize] = &[$($index),+];
+            let mut i = 0;
+            while i < states.len() {
+                assert!(states[i] == i, "states must be numbered in order from 0");
+                i += 1;
+            }
+
+            let used: &[usize] = &[$($start,)+ $($endpoint),*];
+            let mut i = 0;
+            while i < used.len() {
+                assert!(used[i] < states.len(), "undefined state");
+                i += 1;
+            }
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __state_data {
+    () => {
+        ()
+    };
+    ($data:expr) => {
+        $data
+    };
+}
+
+#[test]
+fn test_dfa_macro() {
+    use crate::dfa::DFA;
+
+    let mut expected = DFA::new(false);
+    expected.get_state_mut(0).unwrap().set_transition('/', 1);
+    expected.add_state(false).set_transition('/', 2);
+    expected.add_state(true).set_transition(' ', 2);
+
+    let dfa = dfa! {
+        start: 0;
+        states: { 0 => false, 1 => false, 2 => true };
+        edges: { 0 -'/'-> 1, 1 -'/'-> 2, 2 -' '-> 2 }
+    };
+    assert_eq!(dfa, expected);
+    assert!(dfa.accepts("//  ".chars(), |&v| v));
+    assert!(dfa.traverse("/ ".chars()).is_none());
+}
+
+#[test]
+fn test_nfa_macro() {
+    let nfa = nfa! {
+        start: 0;
+        states: { 0 => false; 0, 1 => false; 1, 2 => false; 2, 3 => true; 3 };
+        edges: { 0 -'a'-> 1, 0 -'a'-> 2, 1 -'b'-> 3, 2 -'c'-> 3 }
+    };
+    assert_eq!(nfa.traverse(['a'].iter()).len(), 2);
+    assert!(nfa.accepts(['a', 'b'].iter()));
+    assert!(nfa.accepts(['a', 'c'].iter()));
+    assert!(!nfa.accepts(['a'].iter()));
+    assert!(nfa.iter_states().all(|(i, st)| st.data == i));
+
+    let nfa = nfa! {
+        start: 0, 1;
+        states: { 0 => false, 1 => false, 2 => true };
+        edges: { 0 -'x'-> 2, 1 -'y'-> 2 }
+    };
+    assert_eq!(nfa.starts(), &[0, 1]);
+    assert!(nfa.accepts(['y'].iter()));
+}
@@ -0,0 +1,152 @@
+use alloc::{collections::VecDeque, string::String, vec, vec::Vec};
+
+use crate::{state::State, transition::RealTransition};
+
+/// Finds every occurrence of a set of keywords in a single pass over some text.
+///
+/// The keywords are stored in a trie, and each state also has a failure link to the state of
+/// its longest proper suffix that is still in the trie. Each state holds the indices of every
+/// keyword that ends there, including those found through its failure links.
+#[derive(Clone, Debug)]
+pub struct MultiMatcher {
+    states: Vec<State<Vec<usize>, RealTransition<char>>>,
+    fail: Vec<usize>,
+    keywords: Vec<String>,
+}
+
+impl MultiMatcher {
+    /// Builds a matcher for the given keywords.
+    pub fn new<I>(keywords: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let mut matcher = MultiMatcher {
+            states: vec![State::new(false, Vec::new())],
+            fail: vec![0],
+            keywords: Vec::new(),
+        };
+
+        for keyword in keywords {
+            let keyword = keyword.into();
+            let mut state = 0;
+            for c in keyword.chars() {
+                state = match matcher.goto(state, c) {
+                    Some(next) => next,
+                    None => {
+                        let next = matcher.states.len();
+                        matcher.states.push(State::new(false, Vec::new()));
+                        matcher.fail.push(0);
+                        matcher.states[state].add_transition(RealTransition::new(c, next));
+                        next
+                    }
+                };
+            }
+            if !matcher.states[state].finish {
+                matcher.states[state].finish = true;
+                matcher.states[state].data.push(matcher.keywords.len());
+                matcher.keywords.push(keyword);
+            }
+        }
+
+        matcher.link_failures();
+        matcher
+    }
+
+    /// Computes the failure links breadth first, so the link of every shallower state is known
+    /// by the time it is needed.
+    fn link_failures(&mut self) {
+        let mut queue: VecDeque<usize> = self.states[0]
+            .transitions
+            .iter()
+            .map(|tr| tr.dest)
+            .collect();
+
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<(char, usize)> = self.states[state]
+                .transitions
+                .iter()
+                .map(|tr| (tr.symbol, tr.dest))
+                .collect();
+
+            for (c, next) in edges {
+                let mut fallback = self.fail[state];
+                let fail = loop {
+                    if let Some(dest) = self.goto(fallback, c) {
+                        break dest;
+                    }
+                    if fallback == 0 {
+                        break 0;
+                    }
+                    fallback = self.fail[fallback];
+                };
+
+                self.fail[next] = fail;
+                let inherited = self.states[fail].data.clone();
+                self.states[next].data.extend(inherited);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    /// Follows the trie edge for `c` out of `state`, if there is one.
+    fn goto(&self, state: usize, c: char) -> Option<usize> {
+        self.states[state]
+            .transitions
+            .iter()
+            .find(|tr| tr.symbol == c)
+            .map(|tr| tr.dest)
+    }
+
+    /// Gets the keywords this matcher looks for, without duplicates.
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    /// Finds every occurrence of every keyword in `haystack`, including overlapping ones.
+    ///
+    /// Each match is the byte offset just past its end, along with the keyword. Matches are
+    /// ordered by where they end, and longer keywords come first among those ending together.
+    pub fn find_all(&self, haystack: &str) -> Vec<(usize, &str)> {
+        let mut matches = Vec::new();
+        let mut state = 0;
+
+        for (i, c) in haystack.char_indices() {
+            state = loop {
+                if let Some(next) = self.goto(state, c) {
+                    break next;
+                }
+                if state == 0 {
+                    break 0;
+                }
+                state = self.fail[state];
+            };
+
+            let end = i + c.len_utf8();
+            for &keyword in self.states[state].data.iter() {
+                matches.push((end, self.keywords[keyword].as_str()));
+            }
+        }
+
+        matches
+    }
+}
+
+#[test]
+fn test_find_all() {
+    let matcher = MultiMatcher::new(["he", "she", "his", "hers"]);
+
+    assert_eq!(
+        matcher.find_all("ushers"),
+        vec![(4, "she"), (4, "he"), (6, "hers")]
+    );
+    assert_eq!(matcher.find_all("ahishers").len(), 4);
+    assert!(matcher.find_all("hxs").is_empty());
+
+    let repeated = MultiMatcher::new(["a", "aa", "a"]);
+    assert_eq!(repeated.keywords(), &["a", "aa"]);
+    assert_eq!(
+        repeated.find_all("aaa"),
+        vec![(1, "a"), (2, "aa"), (2, "a"), (3, "aa"), (3, "a")]
+    );
+}
@@ -1,6 +1,23 @@
-use std::fmt::Display;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt::Display, hash::Hash};
 
-use crate::{nfae::NFAe, state::State, transition::RealTransition};
+use crate::{
+    bitset_nfa::{BitsetNFA, TooManyStatesError},
+    collections::{HashMap, HashSet},
+    dfa::DFA,
+    dot::DotWriter,
+    graph,
+    nfae::NFAe,
+    regex::Expr,
+    state::State,
+    stats::AutomatonStats,
+    transition::{MaybeEpsilonTransition, RealTransition, TransitionConversionError},
+};
 
 #[derive(Debug)]
 pub enum NFABuilderError {
@@ -8,23 +25,30 @@ pub enum NFABuilderError {
     MissingStates,
     MissingFinish,
     InvalidStartIndex,
+    /// A transition points at a state that does not exist.
+    InvalidTransitionTarget(usize),
 }
 
 impl Display for NFABuilderError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::MissingStartIndex => write!(f, "must specify a start index"),
             Self::MissingStates => write!(f, "must have at least one state"),
             Self::MissingFinish => write!(f, "must have at least one finish"),
             Self::InvalidStartIndex => write!(f, "start index must be valid"),
+            Self::InvalidTransitionTarget(dest) => {
+                write!(f, "transition target {} must be a valid state", dest)
+            }
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct NFABuilder<L, S> {
     pub(crate) states: Vec<State<S, RealTransition<L>>>,
-    pub(crate) start: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) starts: Vec<usize>,
 }
 
 impl<L, S> NFABuilder<L, S> {
@@ -33,15 +57,40 @@ impl<L, S> NFABuilder<L, S> {
         self
     }
 
+    /// Adds a state written with NFA-e transitions, failing without adding it if any of them
+    /// is an epsilon transition.
+    pub fn add_epsilon_free_state(
+        &mut self,
+        state: State<S, MaybeEpsilonTransition<L>>,
+    ) -> Result<&mut Self, TransitionConversionError> {
+        self.states.push(State::try_from(state)?);
+        Ok(self)
+    }
+
+    /// Adds a state, returning its index.
+    pub fn add_state_indexed(&mut self, state: State<S, RealTransition<L>>) -> usize {
+        self.states.push(state);
+        self.states.len() - 1
+    }
+
+    /// Makes `start` the only start state.
     pub fn set_start(&mut self, start: usize) -> &mut Self {
-        self.start = Some(start);
+        self.starts = vec![start];
+        self
+    }
+
+    /// Adds another start state.
+    pub fn add_start(&mut self, start: usize) -> &mut Self {
+        if !self.starts.contains(&start) {
+            self.starts.push(start);
+        }
         self
     }
 
     pub fn build(self) -> Result<NFA<L, S>, NFABuilderError> {
-        let Some(start) = self.start else {
+        if self.starts.is_empty() {
             return Err(NFABuilderError::MissingStartIndex);
-        };
+        }
 
         if self.states.is_empty() {
             return Err(NFABuilderError::MissingStates);
@@ -52,37 +101,62 @@ impl<L, S> NFABuilder<L, S> {
             return Err(NFABuilderError::MissingFinish);
         }
 
-        if start >= self.states.len() {
+        if self.starts.iter().any(|&start| start >= self.states.len()) {
             return Err(NFABuilderError::InvalidStartIndex);
         }
 
+        if let Some(dest) = self
+            .states
+            .iter()
+            .flat_map(|st| st.transitions.iter().map(|tr| tr.dest()))
+            .find(|&dest| dest >= self.states.len())
+        {
+            return Err(NFABuilderError::InvalidTransitionTarget(dest));
+        }
+
         Ok(NFA {
-            start,
+            starts: self.starts,
             states: self.states,
         })
     }
 }
 
 impl<L, S> From<NFA<L, S>> for NFABuilder<L, S> {
-    fn from(NFA { states, start }: NFA<L, S>) -> Self {
-        Self {
-            states,
-            start: Some(start),
-        }
+    fn from(NFA { states, starts }: NFA<L, S>) -> Self {
+        Self { states, starts }
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "NFABuilder<L, S>"))]
 pub struct NFA<L, S> {
     pub(crate) states: Vec<State<S, RealTransition<L>>>,
-    pub(crate) start: usize,
+    pub(crate) starts: Vec<usize>,
 }
 
-impl<L, S> From<NFAe<L, S>> for NFA<L, S>
-where
-    L: Clone,
-{
-    fn from(value: NFAe<L, S>) -> Self {
-        value.into_nfa()
+impl<L, S> TryFrom<NFABuilder<L, S>> for NFA<L, S> {
+    type Error = NFABuilderError;
+
+    fn try_from(builder: NFABuilder<L, S>) -> Result<Self, Self::Error> {
+        builder.build()
+    }
+}
+
+/// Converts an NFA-e that has no epsilon transitions, keeping every state as it is.
+/// [`NFAe::into_nfa`] removes epsilon transitions first.
+impl<L, S> TryFrom<NFAe<L, S>> for NFA<L, S> {
+    type Error = TransitionConversionError;
+
+    fn try_from(value: NFAe<L, S>) -> Result<Self, Self::Error> {
+        Ok(NFA {
+            states: value
+                .states
+                .into_iter()
+                .map(State::try_from)
+                .collect::<Result<_, _>>()?,
+            starts: vec![value.start],
+        })
     }
 }
 
@@ -91,8 +165,205 @@ impl<L, S> NFA<L, S> {
         self.states.get(s)
     }
 
+    /// Gets the first start state.
     pub fn get_start(&self) -> &State<S, RealTransition<L>> {
-        self.get_state(self.start).unwrap()
+        self.get_state(self.starts[0]).unwrap()
+    }
+
+    /// Gets the indices of every start state.
+    pub fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// Groups the states into strongly connected components, treating every transition as an
+    /// edge regardless of its symbol. Each component is sorted, and comes after every
+    /// component reachable from it.
+    pub fn sccs(&self) -> Vec<Vec<usize>> {
+        let adjacency: Vec<Vec<usize>> = self
+            .states
+            .iter()
+            .map(|st| st.transitions.iter().map(|tr| tr.dest()).collect())
+            .collect();
+        graph::sccs(&adjacency)
+    }
+
+    /// Iterates over every state along with its index.
+    pub fn iter_states(&self) -> impl Iterator<Item = (usize, &State<S, RealTransition<L>>)> {
+        self.states.iter().enumerate()
+    }
+
+    /// Transforms the data held by every state, keeping the transitions unchanged.
+    pub fn map_data<T>(self, mut f: impl FnMut(S) -> T) -> NFA<L, T> {
+        NFA {
+            states: self
+                .states
+                .into_iter()
+                .map(|st| st.map_data(&mut f))
+                .collect(),
+            starts: self.starts,
+        }
+    }
+
+    /// Transforms the symbol of every transition, keeping the states unchanged.
+    pub fn map_labels<M>(self, mut f: impl FnMut(L) -> M) -> NFA<M, S> {
+        NFA {
+            states: self
+                .states
+                .into_iter()
+                .map(|st| State {
+                    data: st.data,
+                    transitions: st
+                        .transitions
+                        .into_iter()
+                        .map(|tr| tr.map_symbol(&mut f))
+                        .collect(),
+                    finish: st.finish,
+                })
+                .collect(),
+            starts: self.starts,
+        }
+    }
+
+    /// Returns true if no finish state is reachable from a start state,
+    /// meaning this NFA accepts no strings.
+    pub fn is_empty(&self) -> bool {
+        let mut seen: HashSet<usize> = self.starts.iter().copied().collect();
+        let mut stack = self.starts.clone();
+
+        while let Some(index) = stack.pop() {
+            let state = &self.states[index];
+            if state.is_finish() {
+                return false;
+            }
+            for transition in state.transitions.iter() {
+                if seen.insert(transition.dest()) {
+                    stack.push(transition.dest());
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Builds an NFA-e accepting the reverse of every string this NFA accepts.
+    ///
+    /// Every transition is flipped and the old starts become the only finishes. A new start
+    /// state, holding `S::default()`, has epsilon transitions to each old finish state.
+    pub fn reverse(self) -> NFAe<L, S>
+    where
+        S: Default,
+    {
+        let new_start = self.states.len();
+        let mut reversed: Vec<State<S, MaybeEpsilonTransition<L>>> = Vec::new();
+        let mut flipped: Vec<Vec<MaybeEpsilonTransition<L>>> =
+            (0..self.states.len()).map(|_| Vec::new()).collect();
+        let mut start = State::new(false, S::default());
+
+        for (i, state) in self.states.into_iter().enumerate() {
+            if state.is_finish() {
+                start.add_transition(MaybeEpsilonTransition::new_epsilon(i));
+            }
+
+            for transition in state.transitions {
+                flipped[transition.dest]
+                    .push(MaybeEpsilonTransition::new_symbol(transition.symbol, i));
+            }
+
+            reversed.push(State::new(self.starts.contains(&i), state.data));
+        }
+
+        for (state, transitions) in reversed.iter_mut().zip(flipped) {
+            state.add_transitions(transitions.into_iter());
+        }
+
+        reversed.push(start);
+
+        NFAe {
+            states: reversed,
+            start: new_start,
+        }
+    }
+}
+
+impl<L, S> NFA<L, S>
+where
+    L: Display,
+{
+    /// Renders this NFA in the Graphviz DOT language.
+    /// Finish states are drawn as double circles.
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new(self.starts.iter().copied());
+
+        for (i, state) in self.states.iter().enumerate() {
+            dot.node(i, i, state.is_finish());
+        }
+
+        for (i, state) in self.states.iter().enumerate() {
+            for transition in state.transitions.iter() {
+                dot.edge(i, transition.dest(), transition.symbol(), false);
+            }
+        }
+
+        dot.finish()
+    }
+
+    /// Converts this NFA into an equivalent regular expression by state elimination, using
+    /// the syntax accepted by [`crate::regex::parse`]. Returns `None` if no finish state can
+    /// be reached, since that syntax cannot express the empty language.
+    pub fn to_regex(&self) -> Option<String> {
+        // fresh start and accept states, joined to the automaton by epsilon edges
+        let start = self.states.len();
+        let accept = start + 1;
+
+        let mut edges: BTreeMap<(usize, usize), Expr> = BTreeMap::new();
+        let add_edge = |edges: &mut BTreeMap<(usize, usize), Expr>, key, expr: Expr| {
+            let expr = match edges.remove(&key) {
+                Some(existing) => existing.alternate(expr),
+                None => expr,
+            };
+            edges.insert(key, expr);
+        };
+
+        for &first in self.starts.iter() {
+            add_edge(&mut edges, (start, first), Expr::Epsilon);
+        }
+        for (i, state) in self.states.iter().enumerate() {
+            for transition in state.transitions.iter() {
+                let expr = Expr::symbol(transition.symbol());
+                add_edge(&mut edges, (i, transition.dest()), expr);
+            }
+            if state.is_finish() {
+                add_edge(&mut edges, (i, accept), Expr::Epsilon);
+            }
+        }
+
+        // eliminate every original state, rerouting each path through it around it
+        for k in 0..self.states.len() {
+            let looped = edges.remove(&(k, k)).map(Expr::star);
+            let incoming: Vec<(usize, Expr)> = edges
+                .iter()
+                .filter(|((_, to), _)| *to == k)
+                .map(|(&(from, _), expr)| (from, expr.clone()))
+                .collect();
+            let outgoing: Vec<(usize, Expr)> = edges
+                .iter()
+                .filter(|((from, _), _)| *from == k)
+                .map(|(&(_, to), expr)| (to, expr.clone()))
+                .collect();
+            edges.retain(|&(from, to), _| from != k && to != k);
+
+            for (from, before) in incoming.iter() {
+                for (to, after) in outgoing.iter() {
+                    let mut expr = before.clone();
+                    if let Some(looped) = &looped {
+                        expr = expr.concat(looped.clone());
+                    }
+                    add_edge(&mut edges, (*from, *to), expr.concat(after.clone()));
+                }
+            }
+        }
+
+        edges.remove(&(start, accept)).map(|expr| expr.to_string())
     }
 }
 
@@ -100,37 +371,314 @@ impl<'b, L: 'b, S> NFA<L, S>
 where
     L: PartialEq,
 {
-    pub fn traverse_from<'a, I: 'b>(
+    /// Simulates this NFA on the given symbols, starting from state `from`.
+    /// Returns the states that are reached after consuming every symbol, each at most once.
+    pub fn traverse_from<'a, I>(
         &'a self,
         from: usize,
-        mut symbols: I,
-    ) -> Vec<&State<S, RealTransition<L>>>
+        symbols: I,
+    ) -> Vec<&'a State<S, RealTransition<L>>>
     where
-        I: Iterator<Item = &'b L> + Clone,
+        I: Iterator<Item = &'b L> + Clone + 'b,
     {
-        let Some(curr) = self.get_state(from) else {
+        if self.get_state(from).is_none() {
             return Vec::new();
-        };
+        }
 
-        let mut ends = Vec::new();
+        self.traverse_frontier(vec![from], symbols)
+    }
 
-        let Some(symbol) = symbols.next() else {
-            return vec![curr];
-        };
+    /// Simulates this NFA on the given symbols from every start state.
+    /// Returns the distinct states that are reached after consuming every symbol.
+    pub fn traverse<'a, I>(&'a self, symbols: I) -> Vec<&'a State<S, RealTransition<L>>>
+    where
+        I: Iterator<Item = &'b L> + Clone + 'b,
+    {
+        self.traverse_frontier(self.starts.clone(), symbols)
+    }
+
+    /// Simulates this NFA on the given symbols from every start state, reusing the given
+    /// buffers instead of allocating. Returns the sorted indices of the distinct states that
+    /// are reached after consuming every symbol, which are left in `frontier`.
+    pub fn traverse_into<'f, I>(
+        &self,
+        symbols: I,
+        frontier: &'f mut Vec<usize>,
+        next: &mut Vec<usize>,
+    ) -> &'f [usize]
+    where
+        I: Iterator<Item = &'b L> + Clone + 'b,
+    {
+        frontier.clear();
+        frontier.extend(self.starts.iter().copied());
+        // the starts are in the order they were added, which matters if no symbol is consumed
+        frontier.sort_unstable();
+        frontier.dedup();
+        self.advance(symbols, frontier, next);
+        frontier
+    }
+
+    /// Simulates this NFA on the given symbols from a set of distinct states.
+    fn traverse_frontier<'a, I>(
+        &'a self,
+        mut frontier: Vec<usize>,
+        symbols: I,
+    ) -> Vec<&'a State<S, RealTransition<L>>>
+    where
+        I: Iterator<Item = &'b L> + Clone + 'b,
+    {
+        self.advance(symbols, &mut frontier, &mut Vec::new());
+        frontier
+            .into_iter()
+            .filter_map(|index| self.get_state(index))
+            .collect()
+    }
+
+    /// Replaces a set of distinct states with the sorted, distinct states reached from them
+    /// on the given symbols. `next` is scratch space.
+    fn advance<I>(&self, symbols: I, frontier: &mut Vec<usize>, next: &mut Vec<usize>)
+    where
+        I: Iterator<Item = &'b L> + Clone + 'b,
+    {
+        for symbol in symbols {
+            if frontier.is_empty() {
+                break;
+            }
+
+            // advance every state in the frontier along the symbol
+            next.clear();
+            for &index in frontier.iter() {
+                let Some(state) = self.get_state(index) else {
+                    continue;
+                };
+                next.extend(
+                    state
+                        .transitions
+                        .iter()
+                        .filter(|tr| tr.symbol() == symbol)
+                        .map(|tr| tr.dest()),
+                );
+            }
+            next.sort_unstable();
+            next.dedup();
+
+            core::mem::swap(frontier, next);
+        }
+    }
+
+    /// Returns whether some state reached from a start state on the given symbols is a finish.
+    pub fn accepts<I>(&self, symbols: I) -> bool
+    where
+        I: Iterator<Item = &'b L> + Clone + 'b,
+    {
+        self.traverse(symbols).iter().any(|st| st.is_finish())
+    }
+}
+
+impl<L, S> NFA<L, S>
+where
+    L: Eq + Hash,
+{
+    /// Returns every symbol used by a transition of this NFA.
+    pub fn alphabet(&self) -> HashSet<&L> {
+        self.states
+            .iter()
+            .flat_map(|st| st.transitions.iter().map(|tr| tr.symbol()))
+            .collect()
+    }
 
-        let next_indices = curr.next(symbol);
-        for index in next_indices {
-            ends.extend(self.traverse_from(index, symbols.clone()));
+    /// Removes every transition that repeats an earlier transition of the same state, with
+    /// the same symbol and destination.
+    pub fn dedupe_transitions(&mut self) {
+        for state in self.states.iter_mut() {
+            let keep: Vec<bool> = {
+                let mut seen = HashSet::new();
+                state
+                    .transitions
+                    .iter()
+                    .map(|tr| seen.insert((tr.symbol(), tr.dest())))
+                    .collect()
+            };
+            let mut keep = keep.into_iter();
+            state.transitions.retain(|_| keep.next().unwrap());
         }
+    }
+
+    /// Compiles this NFA into a [`BitsetNFA`], which runs with bitwise operations instead of
+    /// lists of states. Fails if there are more states than fit in a bitset.
+    pub fn compile_bitset(self) -> Result<BitsetNFA<L>, TooManyStatesError> {
+        BitsetNFA::from_nfa(self)
+    }
 
-        ends
+    /// Counts the states, transitions, and finish states of this NFA.
+    pub fn stats(&self) -> AutomatonStats {
+        let alphabet_len = self.alphabet().len();
+        AutomatonStats {
+            num_states: self.states.len(),
+            num_transitions: self.states.iter().map(|st| st.transitions.len()).sum(),
+            num_epsilon_transitions: 0,
+            num_accepting: self.states.iter().filter(|st| st.is_finish()).count(),
+            is_complete: self.states.iter().all(|st| {
+                let symbols: HashSet<&L> = st.transitions.iter().map(|tr| tr.symbol()).collect();
+                symbols.len() == alphabet_len
+            }),
+            max_out_degree: self
+                .states
+                .iter()
+                .map(|st| st.transitions.len())
+                .max()
+                .unwrap_or(0),
+        }
     }
+}
+
+impl<L, S> NFA<L, S>
+where
+    L: Ord + Hash,
+{
+    /// Merges states that are both finishes or both not, and have the same set of transitions,
+    /// repeating until no more states can be merged. The language is unchanged, and each
+    /// merged state keeps the data of the lowest-numbered state it replaces.
+    ///
+    /// Returns the new index of every old state.
+    pub fn merge_equivalent_states(&mut self) -> Vec<usize> {
+        let mut renumbering: Vec<usize> = (0..self.states.len()).collect();
+
+        loop {
+            // every state is replaced by the first state with the same finish flag and edges
+            let representatives: Vec<usize> = {
+                let mut firsts = HashMap::new();
+                self.states
+                    .iter()
+                    .enumerate()
+                    .map(|(i, state)| {
+                        let mut edges: Vec<(&L, usize)> = state
+                            .transitions
+                            .iter()
+                            .map(|tr| (tr.symbol(), tr.dest()))
+                            .collect();
+                        edges.sort_unstable();
+                        edges.dedup();
+                        *firsts.entry((state.is_finish(), edges)).or_insert(i)
+                    })
+                    .collect()
+            };
 
-    pub fn traverse<'a, I: 'b>(&'a self, symbols: I) -> Vec<&State<S, RealTransition<L>>>
+            if representatives.iter().enumerate().all(|(i, &rep)| i == rep) {
+                return renumbering;
+            }
+
+            let mut new_indices = vec![0; representatives.len()];
+            let mut kept = 0;
+            for (i, &rep) in representatives.iter().enumerate() {
+                if i == rep {
+                    new_indices[i] = kept;
+                    kept += 1;
+                }
+            }
+            let new_index = |i: usize| new_indices[representatives[i]];
+
+            let states = core::mem::take(&mut self.states);
+            for (i, mut state) in states.into_iter().enumerate() {
+                if representatives[i] != i {
+                    continue;
+                }
+                for transition in state.transitions.iter_mut() {
+                    transition.dest = new_index(transition.dest);
+                }
+                self.states.push(state);
+            }
+
+            let mut starts = Vec::new();
+            for &start in self.starts.iter() {
+                if !starts.contains(&new_index(start)) {
+                    starts.push(new_index(start));
+                }
+            }
+            self.starts = starts;
+
+            for index in renumbering.iter_mut() {
+                *index = new_index(*index);
+            }
+            self.dedupe_transitions();
+        }
+    }
+}
+
+impl<L, S> NFA<L, S>
+where
+    L: Clone + Eq + Hash,
+{
+    /// Finds the states that the given symbol transitions to from any of a set of states.
+    pub(crate) fn step_set(&self, set: &BTreeSet<usize>, symbol: &L) -> BTreeSet<usize> {
+        set.iter()
+            .flat_map(|&s| self.states[s].next(symbol))
+            .collect()
+    }
+
+    /// Converts this NFA into a DFA using the powerset construction.
+    /// Each DFA state holds whether it is a finish state and the data of the NFA states it is made of.
+    pub fn into_dfa(self) -> DFA<L, (bool, Vec<S>)>
     where
-        I: Iterator<Item = &'b L> + Clone,
+        S: Clone,
     {
-        self.traverse_from(self.start, symbols)
+        self.into_dfa_with(|states| {
+            let finish = states.iter().any(|st| st.is_finish());
+            let data = states.iter().map(|st| st.data.clone()).collect();
+            (finish, data)
+        })
+    }
+
+    /// Converts this NFA into a DFA using the powerset construction.
+    /// `merge` is given the NFA states that make up each DFA state and produces its value.
+    pub fn into_dfa_with<T, F>(self, mut merge: F) -> DFA<L, T>
+    where
+        F: FnMut(&[&State<S, RealTransition<L>>]) -> T,
+    {
+        let alphabet = self.alphabet();
+
+        let start: BTreeSet<usize> = self.starts.iter().copied().collect();
+        let mut indices: HashMap<BTreeSet<usize>, usize> = HashMap::from([(start.clone(), 0)]);
+        let mut sets = vec![start];
+        let mut transitions = Vec::new();
+
+        // sets are appended as they are discovered, so this visits every reachable set once
+        let mut i = 0;
+        while i < sets.len() {
+            let mut state_transitions = HashMap::new();
+
+            for &symbol in alphabet.iter() {
+                let next = self.step_set(&sets[i], symbol);
+
+                if next.is_empty() {
+                    continue;
+                }
+
+                let dest = *indices.entry(next).or_insert_with_key(|next| {
+                    sets.push(next.clone());
+                    sets.len() - 1
+                });
+
+                state_transitions.insert(symbol.clone(), dest);
+            }
+
+            transitions.push(state_transitions);
+            i += 1;
+        }
+
+        let values = sets
+            .iter()
+            .map(|set| {
+                let states: Vec<_> = set.iter().map(|&s| &self.states[s]).collect();
+                merge(&states)
+            })
+            .collect();
+
+        DFA {
+            values,
+            transitions,
+            start: 0,
+        }
     }
 }
 
@@ -156,11 +704,592 @@ fn test_nfa_traverse() {
     let nfa = nfa.build().unwrap();
 
     assert!(
-        nfa.traverse(vec!['a'].iter())
+        nfa.traverse(['a'].iter())
             .iter()
             .map(|st| st.data)
             .collect::<Vec<_>>()
             == vec![1, 2]
     );
-    assert!(nfa.traverse(vec!['a', 'b'].iter()).first().unwrap().data == 3);
+    assert!(nfa.traverse(['a', 'b'].iter()).first().unwrap().data == 3);
+}
+
+#[test]
+fn test_nfa_accepts() {
+    // the machine from `test_nfa_traverse`, accepting "ab" and "ac"
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, 0);
+    let mut x = State::new(false, 1);
+    let mut y = State::new(false, 2);
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('a', 2));
+    x.add_transition(RealTransition::new('b', 3));
+    y.add_transition(RealTransition::new('c', 3));
+    nfa.add_state(start)
+        .add_state(x)
+        .add_state(y)
+        .add_state(State::new(true, 3));
+    nfa.set_start(0);
+    let nfa = nfa.build().unwrap();
+
+    assert!(nfa.accepts(['a', 'b'].iter()));
+    assert!(nfa.accepts(['a', 'c'].iter()));
+    assert!(!nfa.accepts(['a'].iter()));
+    assert!(!nfa.accepts([].iter()));
+}
+
+#[test]
+fn test_iter_states() {
+    // the machine from `test_nfa_traverse`, where each state holds its index
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, 0);
+    let mut x = State::new(false, 1);
+    let mut y = State::new(false, 2);
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('a', 2));
+    x.add_transition(RealTransition::new('b', 3));
+    y.add_transition(RealTransition::new('c', 3));
+    nfa.add_state(start)
+        .add_state(x)
+        .add_state(y)
+        .add_state(State::new(true, 3));
+    nfa.set_start(0);
+    let nfa = nfa.build().unwrap();
+
+    assert_eq!(nfa.iter_states().count(), 4);
+    assert_eq!(
+        nfa.iter_states()
+            .map(|(_, st)| st.transitions.len())
+            .sum::<usize>(),
+        4
+    );
+    assert!(nfa.iter_states().all(|(i, st)| st.data == i));
+}
+
+#[test]
+fn test_state_transitions() {
+    let nfa = nfa! {
+        start: 0;
+        states: { 0 => false, 1 => false, 2 => true };
+        edges: { 0 -'a'-> 1, 0 -'b'-> 2, 1 -'c'-> 2 }
+    };
+
+    let start = nfa.get_start();
+    assert_eq!(start.transition_count(), 2);
+    let edges: Vec<(char, usize)> = start
+        .transitions()
+        .iter()
+        .map(|tr| (*tr.symbol(), tr.dest()))
+        .collect();
+    assert_eq!(edges, [('a', 1), ('b', 2)]);
+    assert_eq!(nfa.get_state(2).unwrap().transitions(), &[]);
+}
+
+#[test]
+fn test_dedupe_transitions() {
+    let mut nfa = nfa! {
+        start: 0;
+        states: { 0 => false, 1 => true };
+        edges: { 0 -'a'-> 1, 0 -'a'-> 1, 0 -'b'-> 1, 1 -'a'-> 1, 1 -'a'-> 0 }
+    };
+    let inputs = ["", "a", "aa", "ab", "b", "ba"];
+    let before: Vec<bool> = inputs
+        .iter()
+        .map(|input| nfa.accepts(input.chars().collect::<Vec<_>>().iter()))
+        .collect();
+
+    nfa.dedupe_transitions();
+
+    assert_eq!(nfa.get_start().transition_count(), 2);
+    assert_eq!(nfa.get_state(1).unwrap().transition_count(), 2);
+    let after: Vec<bool> = inputs
+        .iter()
+        .map(|input| nfa.accepts(input.chars().collect::<Vec<_>>().iter()))
+        .collect();
+    assert_eq!(after, before);
+}
+
+#[test]
+fn test_merge_equivalent_states() {
+    // "ab" or "cb", through 1 and 2, which both lead to a finish on "b"; 3 and 4 are the
+    // finishes, and after they merge, so do 1 and 2
+    let mut nfa = nfa! {
+        start: 0;
+        states: { 0 => false; 0, 1 => false; 1, 2 => false; 2, 3 => true; 3, 4 => true; 4 };
+        edges: { 0 -'a'-> 1, 0 -'c'-> 2, 1 -'b'-> 3, 2 -'b'-> 4 }
+    };
+    let inputs = ["", "a", "ab", "cb", "c", "abb", "b"];
+    let accepts = |nfa: &NFA<char, i32>| -> Vec<bool> {
+        inputs
+            .iter()
+            .map(|input| nfa.accepts(input.chars().collect::<Vec<_>>().iter()))
+            .collect()
+    };
+    let before = accepts(&nfa);
+
+    assert_eq!(nfa.merge_equivalent_states(), vec![0, 1, 1, 2, 2]);
+    assert_eq!(nfa.iter_states().count(), 3);
+    assert_eq!(nfa.get_state(1).unwrap().data, 1);
+    assert_eq!(nfa.get_start().transition_count(), 2);
+    assert_eq!(accepts(&nfa), before);
+
+    // nothing left to merge
+    assert_eq!(nfa.merge_equivalent_states(), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_nfa_multiple_starts() {
+    // "ab" is only accepted from the second start
+    let mut builder = NFABuilder::default();
+    let mut first = State::new(false, 0);
+    let mut second = State::new(false, 1);
+    let mut middle = State::new(false, 2);
+    first.add_transition(RealTransition::new('b', 3));
+    second.add_transition(RealTransition::new('a', 2));
+    middle.add_transition(RealTransition::new('b', 3));
+    builder
+        .add_state(first)
+        .add_state(second)
+        .add_state(middle)
+        .add_state(State::new(true, 3))
+        .set_start(0);
+
+    let single = builder.clone().build().unwrap();
+    assert!(!single.accepts(['a', 'b'].iter()));
+
+    builder.add_start(1).add_start(1);
+    let nfa = builder.build().unwrap();
+    assert_eq!(nfa.starts(), &[0, 1]);
+    assert_eq!(nfa.get_start().data, 0);
+    assert!(nfa.accepts(['a', 'b'].iter()));
+    assert!(nfa.accepts(['b'].iter()));
+    assert!(!nfa.accepts(['a'].iter()));
+    assert_eq!(nfa.to_dot().matches("start -> ").count(), 2);
+
+    let dfa = nfa.clone().into_dfa();
+    assert!(dfa.accepts("ab".chars(), |(finish, _)| *finish));
+    assert!(dfa.accepts("b".chars(), |(finish, _)| *finish));
+
+    let reversed = nfa.reverse().into_nfa().unwrap();
+    assert!(reversed.accepts(['b', 'a'].iter()));
+
+    let empty: NFABuilder<char, ()> = NFABuilder::default();
+    assert!(matches!(
+        empty.build(),
+        Err(NFABuilderError::MissingStartIndex)
+    ));
+}
+
+#[test]
+fn test_nfa_sccs() {
+    let mut builder = NFABuilder::default();
+    let mut a = State::new(false, ());
+    let mut b = State::new(false, ());
+    let mut c = State::new(true, ());
+    a.add_transition(RealTransition::new('x', 1))
+        .add_transition(RealTransition::new('x', 2));
+    b.add_transition(RealTransition::new('y', 0));
+    c.add_transition(RealTransition::new('z', 2));
+    builder.add_state(a).add_state(b).add_state(c).set_start(0);
+
+    assert_eq!(builder.build().unwrap().sccs(), vec![vec![2], vec![0, 1]]);
+}
+
+#[test]
+fn test_try_from_nfae() {
+    use crate::nfae::NFAeBuilder;
+
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    start.add_transition(MaybeEpsilonTransition::new_epsilon(1));
+    builder
+        .add_state(start)
+        .add_state(State::new(true, ()))
+        .set_start(0);
+    let nfae = builder.build().unwrap();
+
+    assert_eq!(
+        NFA::try_from(nfae.clone()).err(),
+        Some(TransitionConversionError::EpsilonNotAllowed)
+    );
+    assert_eq!(
+        TransitionConversionError::EpsilonNotAllowed.to_string(),
+        "transition must have a symbol"
+    );
+
+    // removing the epsilon transitions first makes the conversion succeed
+    let nfa = nfae.into_nfa().unwrap();
+    assert!(nfa.accepts([].iter()));
+    assert!(NFA::try_from(NFAe::from(nfa)).is_ok());
+}
+
+#[test]
+fn test_add_epsilon_free_state() {
+    let mut builder = NFABuilder::default();
+    let mut start = State::new(false, ());
+    start.add_transition(MaybeEpsilonTransition::new_symbol('a', 1));
+    let mut epsilon = State::new(true, ());
+    epsilon.add_transition(MaybeEpsilonTransition::new_epsilon(0));
+
+    builder.add_epsilon_free_state(start).unwrap();
+    assert_eq!(
+        builder.add_epsilon_free_state(epsilon).err(),
+        Some(TransitionConversionError::EpsilonNotAllowed)
+    );
+    builder
+        .add_epsilon_free_state(State::new(true, ()))
+        .unwrap()
+        .set_start(0);
+
+    let nfa = builder.build().unwrap();
+    assert_eq!(nfa.iter_states().count(), 2);
+    assert!(nfa.accepts(['a'].iter()));
+}
+
+#[test]
+fn test_nfa_builder_invalid_target() {
+    let mut builder = NFABuilder::default();
+
+    let mut start = State::new(false, ());
+    start.add_transition(RealTransition::new('a', 1));
+    assert_eq!(builder.add_state_indexed(start), 0);
+
+    let mut finish = State::new(true, ());
+    finish.add_transition(RealTransition::new('b', 5));
+    assert_eq!(builder.add_state_indexed(finish), 1);
+
+    builder.set_start(0);
+
+    assert!(matches!(
+        builder.build(),
+        Err(NFABuilderError::InvalidTransitionTarget(5))
+    ));
+}
+
+#[test]
+fn test_nfa_traverse_unique_ends() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, 0);
+    let mut x = State::new(false, 1);
+    let mut y = State::new(false, 2);
+    let z = State::new(true, 3);
+
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('a', 2));
+
+    x.add_transition(RealTransition::new('b', 3));
+    y.add_transition(RealTransition::new('b', 3));
+
+    nfa.add_state(start).add_state(x).add_state(y).add_state(z);
+
+    nfa.set_start(0);
+
+    let nfa = nfa.build().unwrap();
+
+    let ends = nfa.traverse(['a', 'b'].iter());
+    assert_eq!(ends.len(), 1);
+    assert_eq!(ends[0].data, 3);
+}
+
+#[test]
+fn test_nfa_traverse_into() {
+    let nfa = nfa! {
+        start: 0, 1;
+        states: { 0 => false; 0, 1 => false; 1, 2 => true; 2, 3 => true; 3 };
+        edges: { 0 -'a'-> 2, 0 -'a'-> 3, 1 -'a'-> 2, 2 -'b'-> 0, 3 -'b'-> 1, 3 -'c'-> 3 }
+    };
+
+    let mut frontier = Vec::new();
+    let mut next = Vec::new();
+    for input in ["", "a", "ab", "aba", "ac", "acc", "abc", "x"] {
+        let input: Vec<char> = input.chars().collect();
+        let mut expected: Vec<usize> = nfa
+            .traverse(input.iter())
+            .into_iter()
+            .map(|st| st.data)
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(
+            nfa.traverse_into(input.iter(), &mut frontier, &mut next),
+            expected
+        );
+    }
+
+    // starts added out of order still come back sorted when nothing is consumed
+    let mut builder = NFABuilder::default();
+    builder
+        .add_state(State::new(true, ()))
+        .add_state(State::new(false, ()))
+        .add_state(State::new(true, ()))
+        .set_start(2)
+        .add_start(0)
+        .add_start(1);
+    let nfa: NFA<char, ()> = builder.build().unwrap();
+    assert_eq!(
+        nfa.traverse_into([].iter(), &mut frontier, &mut next),
+        [0, 1, 2]
+    );
+}
+
+#[test]
+fn test_nfa_traverse_long_input() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    let mut finish = State::new(true, ());
+
+    start
+        .add_transition(RealTransition::new('a', 0))
+        .add_transition(RealTransition::new('a', 1));
+    finish
+        .add_transition(RealTransition::new('a', 0))
+        .add_transition(RealTransition::new('a', 1));
+
+    nfa.add_state(start).add_state(finish).set_start(0);
+
+    let nfa = nfa.build().unwrap();
+
+    let input = vec!['a'; 10_000];
+    let ends = nfa.traverse(input.iter());
+
+    assert_eq!(ends.len(), 2);
+    assert!(ends.iter().any(|st| st.is_finish()));
+}
+
+#[test]
+fn test_nfa_reverse() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let b = State::new(true, ());
+
+    start.add_transition(RealTransition::new('a', 1));
+    a.add_transition(RealTransition::new('b', 2));
+
+    nfa.add_state(start).add_state(a).add_state(b).set_start(0);
+
+    let reversed = nfa.build().unwrap().reverse().into_nfa().unwrap();
+    let accepts = |input: &[char]| {
+        reversed
+            .traverse(input.iter())
+            .iter()
+            .any(|st| st.is_finish())
+    };
+
+    assert!(accepts(&['b', 'a']));
+    assert!(!accepts(&['a', 'b']));
+    assert!(!accepts(&['b']));
+}
+
+#[test]
+fn test_nfa_is_empty() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let finish = State::new(true, ());
+
+    start.add_transition(RealTransition::new('a', 1));
+    a.add_transition(RealTransition::new('a', 0));
+
+    nfa.add_state(start)
+        .add_state(a)
+        .add_state(finish)
+        .set_start(0);
+
+    let nfa = nfa.build().unwrap();
+    assert!(nfa.is_empty());
+
+    let mut nfa = NFABuilder::from(nfa);
+    nfa.states[1].add_transition(RealTransition::new('b', 2));
+
+    let nfa = nfa.build().unwrap();
+    assert!(!nfa.is_empty());
+}
+
+#[test]
+fn test_nfa_map_data() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, 1);
+    let finish = State::new(true, 2);
+
+    start.add_transition(RealTransition::new('a', 1));
+
+    nfa.add_state(start).add_state(finish).set_start(0);
+
+    let nfa = nfa.build().unwrap().map_data(|x| x.to_string());
+
+    assert_eq!(nfa.get_start().data, "1");
+    assert_eq!(nfa.traverse(['a'].iter())[0].data, "2");
+}
+
+#[test]
+fn test_nfa_map_labels() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    let finish = State::new(true, ());
+
+    start
+        .add_transition(RealTransition::new('A', 1))
+        .add_transition(RealTransition::new('a', 1));
+
+    nfa.add_state(start).add_state(finish).set_start(0);
+
+    let nfa = nfa
+        .build()
+        .unwrap()
+        .map_labels(|c| c.to_ascii_lowercase() as u8);
+
+    assert_eq!(nfa.traverse([b'a'].iter()).len(), 1);
+    assert!(nfa.traverse([b'A'].iter()).is_empty());
+}
+
+#[test]
+fn test_nfa_to_dot() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    let finish = State::new(true, ());
+
+    start.add_transition(RealTransition::new('a', 1));
+
+    nfa.add_state(start).add_state(finish).set_start(0);
+
+    let dot = nfa.build().unwrap().to_dot();
+
+    assert!(dot.starts_with("digraph {"));
+    assert!(dot.contains("start -> 0;"));
+    assert!(dot.contains("1 [label=\"1\", shape=doublecircle];"));
+    assert!(dot.contains("0 -> 1 [label=\"a\"];"));
+}
+
+#[test]
+fn test_nfa_to_regex() {
+    use crate::regex::compile;
+
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    start.add_transition(RealTransition::new('a', 1));
+    nfa.add_state(start)
+        .add_state(State::new(true, ()))
+        .set_start(0);
+    assert_eq!(nfa.build().unwrap().to_regex().as_deref(), Some("a"));
+
+    // a(b|c), with a loop of b's before the c
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, ());
+    let mut x = State::new(false, ());
+    start.add_transition(RealTransition::new('a', 1));
+    x.add_transition(RealTransition::new('b', 2))
+        .add_transition(RealTransition::new('b', 1))
+        .add_transition(RealTransition::new('c', 3));
+    nfa.add_state(start)
+        .add_state(x)
+        .add_state(State::new(true, ()))
+        .add_state(State::new(true, ()))
+        .set_start(0);
+    let nfa = nfa.build().unwrap();
+
+    let pattern = nfa.to_regex().unwrap();
+    assert!(pattern.contains('b') && pattern.contains('c'));
+
+    // the pattern matches the same strings as the NFA
+    let compiled = compile(&pattern).unwrap().into_nfa().unwrap();
+    let accepts = |nfa: &NFA<char, ()>, input: &[char]| nfa.accepts(input.iter());
+    for input in ["", "a", "ab", "ac", "abb", "abbc", "abcb", "bc"] {
+        let input: Vec<char> = input.chars().collect();
+        assert_eq!(accepts(&nfa, &input), accepts(&compiled, &input));
+    }
+
+    // finish states that cannot be reached leave nothing to express
+    let mut nfa: NFABuilder<char, ()> = NFABuilder::default();
+    nfa.add_state(State::new(false, ()))
+        .add_state(State::new(true, ()))
+        .set_start(0);
+    assert_eq!(nfa.build().unwrap().to_regex(), None);
+}
+
+#[test]
+fn test_nfa_into_dfa() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, 0);
+    let mut x = State::new(false, 1);
+    let mut y = State::new(false, 2);
+    let z = State::new(true, 3);
+
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('a', 2));
+
+    x.add_transition(RealTransition::new('b', 3));
+    y.add_transition(RealTransition::new('c', 3));
+
+    nfa.add_state(start).add_state(x).add_state(y).add_state(z);
+
+    nfa.set_start(0);
+
+    let nfa = nfa.build().unwrap();
+
+    assert_eq!(nfa.alphabet(), HashSet::from([&'a', &'b', &'c']));
+
+    let dfa = nfa.into_dfa();
+
+    assert_eq!(dfa.state_count(), 3);
+    assert_eq!(dfa.alphabet(), HashSet::from([&'a', &'b', &'c']));
+
+    let (finish, data) = dfa.traverse(['a'].iter()).unwrap().value();
+    assert!(!finish);
+    assert_eq!(*data, vec![1, 2]);
+
+    let (finish, data) = dfa.traverse(['a', 'b'].iter()).unwrap().value();
+    assert!(finish);
+    assert_eq!(*data, vec![3]);
+
+    assert_eq!(
+        dfa.traverse(['a', 'b'].iter()).unwrap().index(),
+        dfa.traverse(['a', 'c'].iter()).unwrap().index()
+    );
+    assert!(dfa.traverse(['b'].iter()).is_none());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_nfa_serde_round_trip() {
+    let mut nfa = NFABuilder::default();
+    let mut start = State::new(false, 0);
+    let mut x = State::new(false, 1);
+    let mut y = State::new(false, 2);
+    let z = State::new(true, 3);
+
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('a', 2));
+
+    x.add_transition(RealTransition::new('b', 3));
+    y.add_transition(RealTransition::new('c', 3));
+
+    nfa.add_state(start).add_state(x).add_state(y).add_state(z);
+
+    nfa.set_start(0);
+
+    let nfa = nfa.build().unwrap();
+
+    let json = serde_json::to_string(&nfa).unwrap();
+    let restored: NFA<char, i32> = serde_json::from_str(&json).unwrap();
+
+    for input in [&['a'][..], &['a', 'b'], &['a', 'c'], &['b']] {
+        assert_eq!(
+            nfa.traverse(input.iter())
+                .iter()
+                .map(|st| st.data)
+                .collect::<Vec<_>>(),
+            restored
+                .traverse(input.iter())
+                .iter()
+                .map(|st| st.data)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    let invalid_start = json.replace("\"starts\":[0]", "\"starts\":[7]");
+    assert!(serde_json::from_str::<NFA<char, i32>>(&invalid_start).is_err());
 }
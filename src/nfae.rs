@@ -1,14 +1,20 @@
-use std::collections::HashMap;
+use alloc::{string::String, vec, vec::Vec};
+use core::{fmt::Display, hash::Hash};
 
 use crate::{
+    collections::{HashMap, HashSet},
+    dot::DotWriter,
+    graph,
     nfa::{NFABuilderError, NFA},
     state::State,
-    transition::MaybeEpsilonTransition,
+    stats::AutomatonStats,
+    transition::{MaybeEpsilonTransition, TransitionConversionError},
 };
 
 pub type NFAeBuilderError = NFABuilderError;
 
-#[derive(Default)]
+#[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct NFAeBuilder<L, S> {
     pub(crate) start: Option<usize>,
     pub(crate) states: Vec<State<S, MaybeEpsilonTransition<L>>>,
@@ -44,6 +50,15 @@ impl<L, S> NFAeBuilder<L, S> {
             return Err(NFABuilderError::InvalidStartIndex);
         }
 
+        if let Some(dest) = self
+            .states
+            .iter()
+            .flat_map(|st| st.transitions.iter().map(|tr| tr.dest()))
+            .find(|&dest| dest >= self.states.len())
+        {
+            return Err(NFABuilderError::InvalidTransitionTarget(dest));
+        }
+
         Ok(NFAe {
             start,
             states: self.states,
@@ -78,11 +93,66 @@ fn test_nfae_builder() {
     builder.build().unwrap();
 }
 
+#[test]
+fn test_nfae_builder_invalid_target() {
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+
+    let mut start = State::new(false, ());
+    start.add_transition(MaybeEpsilonTransition::new_epsilon(99));
+
+    builder.set_start(0);
+    builder.add_state(start).add_state(State::new(true, ()));
+
+    assert!(matches!(
+        builder.build(),
+        Err(NFAeBuilderError::InvalidTransitionTarget(99))
+    ));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "NFAeBuilder<L, S>"))]
 pub struct NFAe<L, S> {
     pub(crate) states: Vec<State<S, MaybeEpsilonTransition<L>>>,
     pub(crate) start: usize,
 }
 
+impl<L, S> TryFrom<NFAeBuilder<L, S>> for NFAe<L, S> {
+    type Error = NFAeBuilderError;
+
+    fn try_from(builder: NFAeBuilder<L, S>) -> Result<Self, Self::Error> {
+        builder.build()
+    }
+}
+
+impl<L, S> From<NFA<L, S>> for NFAe<L, S>
+where
+    S: Default,
+{
+    /// Widens an NFA into an NFA-e with the same language. If the NFA has several start
+    /// states, a new start state holding `S::default()` gets epsilon transitions to each.
+    fn from(nfa: NFA<L, S>) -> Self {
+        let mut states: Vec<State<S, MaybeEpsilonTransition<L>>> =
+            nfa.states.into_iter().map(State::from).collect();
+
+        let start = match nfa.starts[..] {
+            [start] => start,
+            _ => {
+                let mut start = State::new(false, S::default());
+                start.add_transitions(
+                    nfa.starts
+                        .iter()
+                        .map(|&s| MaybeEpsilonTransition::new_epsilon(s)),
+                );
+                states.push(start);
+                states.len() - 1
+            }
+        };
+
+        NFAe { states, start }
+    }
+}
+
 impl<L, S> NFAe<L, S> {
     pub fn get_state(&self, state: usize) -> Option<&State<S, MaybeEpsilonTransition<L>>> {
         self.states.get(state)
@@ -103,45 +173,110 @@ impl<L, S> NFAe<L, S> {
         self.states.get(self.start).unwrap()
     }
 
+    /// Iterates over every state along with its index.
+    pub fn iter_states(
+        &self,
+    ) -> impl Iterator<Item = (usize, &State<S, MaybeEpsilonTransition<L>>)> {
+        self.states.iter().enumerate()
+    }
+
     /// Returns a list of states that can be reached from state `s` through epsilon transitions.
+    /// Each state appears at most once, even if the epsilon transitions form a cycle.
     pub fn epsilon_closure(&self, s: usize) -> Vec<&State<S, MaybeEpsilonTransition<L>>> {
-        let mut states = Vec::new();
+        self.epsilon_closure_indices(s)
+            .into_iter()
+            .map(|index| &self.states[index])
+            .collect()
+    }
 
-        // if state is not found, no epsilon-reachable states
-        let Some(state) = self.get_state(s) else {
-            return Vec::new();
-        };
+    /// Returns the indices of the states that can be reached from state `s` through epsilon
+    /// transitions, including `s` itself. Each index appears at most once.
+    pub fn epsilon_closure_indices(&self, s: usize) -> Vec<usize> {
+        let mut closure = Vec::new();
+        self.extend_closure(s, &mut closure, &mut HashSet::new());
+        closure
+    }
 
-        // self is epsilon-reachable
-        states.push(state);
+    /// Adds `from` and every state epsilon-reachable from it to `closure`, skipping states
+    /// already in `seen` and states that do not exist.
+    fn extend_closure(&self, from: usize, closure: &mut Vec<usize>, seen: &mut HashSet<usize>) {
+        let mut stack = vec![from];
+        while let Some(index) = stack.pop() {
+            let Some(state) = self.get_state(index) else {
+                continue;
+            };
+            if !seen.insert(index) {
+                continue;
+            }
+            closure.push(index);
+            for transition in state.transitions.iter() {
+                if transition.is_epsilon() {
+                    stack.push(transition.dest());
+                }
+            }
+        }
+    }
+
+    /// Computes the epsilon closure of every state at once. States on an epsilon cycle share
+    /// one closure, and each strongly connected component is visited after every component
+    /// it can reach, so its closure is built from theirs in a single pass.
+    fn epsilon_closures(&self) -> Vec<HashSet<usize>> {
+        let adjacency: Vec<Vec<usize>> = self
+            .states
+            .iter()
+            .map(|st| {
+                st.transitions
+                    .iter()
+                    .filter(|tr| tr.is_epsilon() && tr.dest() < self.states.len())
+                    .map(|tr| tr.dest())
+                    .collect()
+            })
+            .collect();
+
+        let components = graph::sccs(&adjacency);
+        let mut component_of = vec![0; self.states.len()];
+        for (c, members) in components.iter().enumerate() {
+            for &state in members {
+                component_of[state] = c;
+            }
+        }
 
-        // add state to closure if epsilon-reachable
-        for transition in &state.transitions {
-            if transition.is_epsilon() {
-                states.extend(self.epsilon_closure(transition.dest()));
+        let mut component_closures: Vec<HashSet<usize>> = Vec::with_capacity(components.len());
+        for (c, members) in components.iter().enumerate() {
+            let mut closure: HashSet<usize> = members.iter().copied().collect();
+            for &state in members {
+                for &dest in adjacency[state].iter() {
+                    if component_of[dest] != c {
+                        closure.extend(component_closures[component_of[dest]].iter().copied());
+                    }
+                }
             }
+            component_closures.push(closure);
         }
 
-        states
+        (0..self.states.len())
+            .map(|state| component_closures[component_of[state]].clone())
+            .collect()
     }
 
-    /// Removes non-start states that have no incoming transitions.
-    /// These states are unreachable.
+    /// Removes states that cannot be reached from the start state through any sequence of
+    /// symbol or epsilon transitions.
     pub fn remove_orphan_states(&mut self) {
-        let mut reachable_states = Vec::new();
-        reachable_states.push(self.start);
+        let mut reachable_states = HashSet::from([self.start]);
+        let mut stack = vec![self.start];
 
-        for state in self.states.iter() {
-            for transition in state.transitions.iter() {
-                assert!(!transition.is_epsilon());
-                reachable_states.push(transition.dest());
+        while let Some(index) = stack.pop() {
+            for transition in self.states[index].transitions.iter() {
+                if reachable_states.insert(transition.dest()) {
+                    stack.push(transition.dest());
+                }
             }
         }
 
         let mut new_states: Vec<State<S, MaybeEpsilonTransition<L>>> = Vec::new();
         let mut reassign_map: HashMap<usize, usize> = HashMap::new();
 
-        for (i, state) in std::mem::take(&mut self.states).into_iter().enumerate() {
+        for (i, state) in core::mem::take(&mut self.states).into_iter().enumerate() {
             if reachable_states.contains(&i) {
                 reassign_map.insert(i, new_states.len());
                 new_states.push(state);
@@ -149,6 +284,7 @@ impl<L, S> NFAe<L, S> {
         }
 
         self.states = new_states;
+        self.start = *reassign_map.get(&self.start).unwrap();
 
         for state in self.states.iter_mut() {
             for transition in state.transitions.iter_mut() {
@@ -159,14 +295,305 @@ impl<L, S> NFAe<L, S> {
     }
 }
 
+impl<L, S> NFAe<L, S> {
+    /// Transforms the data held by every state, keeping the transitions unchanged.
+    pub fn map_data<T>(self, mut f: impl FnMut(S) -> T) -> NFAe<L, T> {
+        NFAe {
+            states: self
+                .states
+                .into_iter()
+                .map(|st| st.map_data(&mut f))
+                .collect(),
+            start: self.start,
+        }
+    }
+
+    /// Transforms the symbol of every transition, keeping epsilon transitions and the states
+    /// unchanged.
+    pub fn map_labels<M>(self, mut f: impl FnMut(L) -> M) -> NFAe<M, S> {
+        NFAe {
+            states: self
+                .states
+                .into_iter()
+                .map(|st| State {
+                    data: st.data,
+                    transitions: st
+                        .transitions
+                        .into_iter()
+                        .map(|tr| tr.map_symbol(&mut f))
+                        .collect(),
+                    finish: st.finish,
+                })
+                .collect(),
+            start: self.start,
+        }
+    }
+
+    /// Builds an NFA-e accepting every string of this NFA-e followed by a string of `other`.
+    ///
+    /// Each finish state of this NFA-e stops being a finish and gets an epsilon transition to
+    /// the start of `other`, whose states are appended after this one's.
+    pub fn concat(mut self, other: NFAe<L, S>) -> NFAe<L, S> {
+        let finishes: Vec<usize> = (0..self.states.len())
+            .filter(|&i| self.states[i].is_finish())
+            .collect();
+
+        let other_start = self.append(other);
+
+        for i in finishes {
+            let state = &mut self.states[i];
+            state.finish = false;
+            state.add_transition(MaybeEpsilonTransition::new_epsilon(other_start));
+        }
+
+        self
+    }
+
+    /// Builds an NFA-e accepting every string of this NFA-e or of `other`.
+    ///
+    /// A new start state, holding `S::default()`, gets epsilon transitions to both old starts.
+    pub fn union(mut self, other: NFAe<L, S>) -> NFAe<L, S>
+    where
+        S: Default,
+    {
+        let other_start = self.append(other);
+
+        let mut start = State::new(false, S::default());
+        start
+            .add_transition(MaybeEpsilonTransition::new_epsilon(self.start))
+            .add_transition(MaybeEpsilonTransition::new_epsilon(other_start));
+        self.start = self.states.len();
+        self.states.push(start);
+
+        self
+    }
+
+    /// Builds an NFA-e accepting zero or more repetitions of strings of this NFA-e.
+    ///
+    /// A new start state, which is a finish holding `S::default()`, gets an epsilon transition
+    /// to the old start, and every finish state gets an epsilon transition back to it.
+    pub fn star(mut self) -> NFAe<L, S>
+    where
+        S: Default,
+    {
+        let new_start = self.states.len();
+
+        for state in self.states.iter_mut() {
+            if state.is_finish() {
+                state.add_transition(MaybeEpsilonTransition::new_epsilon(new_start));
+            }
+        }
+
+        let mut start = State::new(true, S::default());
+        start.add_transition(MaybeEpsilonTransition::new_epsilon(self.start));
+        self.states.push(start);
+        self.start = new_start;
+
+        self
+    }
+
+    /// Builds an NFA-e accepting one or more repetitions of strings of this NFA-e,
+    /// by giving every finish state an epsilon transition back to the start.
+    pub fn plus(mut self) -> NFAe<L, S> {
+        let start = self.start;
+
+        for state in self.states.iter_mut() {
+            if state.is_finish() {
+                state.add_transition(MaybeEpsilonTransition::new_epsilon(start));
+            }
+        }
+
+        self
+    }
+
+    /// Builds an NFA-e accepting the empty string or a string of this NFA-e,
+    /// by adding a new start state that is a finish holding `S::default()`.
+    pub fn optional(mut self) -> NFAe<L, S>
+    where
+        S: Default,
+    {
+        let mut start = State::new(true, S::default());
+        start.add_transition(MaybeEpsilonTransition::new_epsilon(self.start));
+        self.start = self.states.len();
+        self.states.push(start);
+
+        self
+    }
+
+    /// Moves the states of `other` to the end of this NFA-e, shifting their transitions.
+    /// Returns the new index of `other`'s start state.
+    fn append(&mut self, other: NFAe<L, S>) -> usize {
+        let offset = self.states.len();
+
+        for mut state in other.states {
+            for transition in state.transitions.iter_mut() {
+                transition.set_dest(transition.dest() + offset);
+            }
+            self.states.push(state);
+        }
+
+        other.start + offset
+    }
+}
+
+impl<L, S> NFAe<L, S>
+where
+    L: Eq + Hash,
+{
+    /// Returns every symbol used by a transition of this NFA-e, skipping epsilon transitions.
+    pub fn alphabet(&self) -> HashSet<&L> {
+        self.states
+            .iter()
+            .flat_map(|st| st.transitions.iter().filter_map(|tr| tr.symbol()))
+            .collect()
+    }
+
+    /// Removes every transition that repeats an earlier transition of the same state, so that
+    /// each state has at most one epsilon transition to any destination, and at most one
+    /// transition per symbol and destination.
+    pub fn dedupe_transitions(&mut self) {
+        for state in self.states.iter_mut() {
+            let keep: Vec<bool> = {
+                let mut seen = HashSet::new();
+                state
+                    .transitions
+                    .iter()
+                    .map(|tr| seen.insert((tr.symbol(), tr.dest())))
+                    .collect()
+            };
+            let mut keep = keep.into_iter();
+            state.transitions.retain(|_| keep.next().unwrap());
+        }
+    }
+
+    /// Counts the states, transitions, and finish states of this NFA-e. It is complete when
+    /// every state has its own transition on every symbol, not counting epsilon transitions.
+    pub fn stats(&self) -> AutomatonStats {
+        let alphabet_len = self.alphabet().len();
+        AutomatonStats {
+            num_states: self.states.len(),
+            num_transitions: self.states.iter().map(|st| st.transitions.len()).sum(),
+            num_epsilon_transitions: self
+                .states
+                .iter()
+                .flat_map(|st| st.transitions.iter())
+                .filter(|tr| tr.is_epsilon())
+                .count(),
+            num_accepting: self.states.iter().filter(|st| st.is_finish()).count(),
+            is_complete: self.states.iter().all(|st| {
+                let symbols: HashSet<&L> =
+                    st.transitions.iter().filter_map(|tr| tr.symbol()).collect();
+                symbols.len() == alphabet_len
+            }),
+            max_out_degree: self
+                .states
+                .iter()
+                .map(|st| st.transitions.len())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl<'b, L: 'b, S> NFAe<L, S>
+where
+    L: PartialEq,
+{
+    /// Simulates this NFA-e on the given symbols from the start state, following epsilon
+    /// transitions before and after every symbol. Returns the distinct states that are
+    /// reached after consuming every symbol.
+    pub fn traverse<'a, I>(&'a self, symbols: I) -> Vec<&'a State<S, MaybeEpsilonTransition<L>>>
+    where
+        I: Iterator<Item = &'b L> + 'b,
+    {
+        let mut seen = HashSet::new();
+        let mut frontier = Vec::new();
+        self.extend_closure(self.start, &mut frontier, &mut seen);
+
+        for symbol in symbols {
+            let mut next = Vec::new();
+            seen.clear();
+            for &index in frontier.iter() {
+                for dest in self.states[index].next(symbol) {
+                    self.extend_closure(dest, &mut next, &mut seen);
+                }
+            }
+
+            frontier = next;
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        frontier
+            .into_iter()
+            .map(|index| &self.states[index])
+            .collect()
+    }
+}
+
+impl<L, S> NFAe<L, S>
+where
+    L: Display,
+{
+    /// Renders this NFA-e in the Graphviz DOT language.
+    /// Finish states are drawn as double circles, and epsilon transitions as dashed `ε` edges.
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new([self.start]);
+
+        for (i, state) in self.states.iter().enumerate() {
+            dot.node(i, i, state.is_finish());
+        }
+
+        for (i, state) in self.states.iter().enumerate() {
+            for transition in state.transitions.iter() {
+                match transition.symbol() {
+                    Some(symbol) => dot.edge(i, transition.dest(), symbol, false),
+                    None => dot.edge(i, transition.dest(), 'ε', true),
+                }
+            }
+        }
+
+        dot.finish()
+    }
+}
+
 impl<L, S> NFAe<L, S>
 where
     L: Clone,
 {
-    /// Calls `epsilon_simplify` on all states.
+    /// Calls `epsilon_simplify` on all states, computing every epsilon closure only once.
     pub fn epsilon_simplify_all(&mut self) {
-        for i in 0..self.states.len() {
-            self.epsilon_simplify(i);
+        let closures = self.epsilon_closures();
+
+        let symbol_transitions: Vec<Vec<MaybeEpsilonTransition<L>>> = self
+            .states
+            .iter()
+            .map(|st| {
+                st.transitions
+                    .iter()
+                    .filter(|tr| !tr.is_epsilon())
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+        let finishes: Vec<bool> = self.states.iter().map(|st| st.is_finish()).collect();
+
+        for (i, closure) in closures.into_iter().enumerate() {
+            // steal transitions in a stable order: the state's own first, then by index
+            let mut closure: Vec<usize> = closure.into_iter().filter(|&j| j != i).collect();
+            closure.sort_unstable();
+
+            let state = &mut self.states[i];
+            state.transitions = symbol_transitions[i].clone();
+            for &j in closure.iter() {
+                state
+                    .transitions
+                    .extend(symbol_transitions[j].iter().cloned());
+                if finishes[j] {
+                    state.finish = true;
+                }
+            }
         }
     }
 
@@ -187,8 +614,8 @@ where
             let new_transitions = epsilon_state
                 .transitions
                 .iter()
-                .cloned()
-                .filter(|tr| !tr.is_epsilon());
+                .filter(|tr| !tr.is_epsilon())
+                .cloned();
             transitions.extend(new_transitions);
             // also mark current state as finish if epsilon-reachable state was finish
             if epsilon_state.is_finish() {
@@ -207,20 +634,17 @@ where
         state.transitions.extend(transitions);
     }
 
-    /// Converts this NFA-e into an NFA.
-    pub fn into_nfa(mut self) -> NFA<L, S> {
+    /// Converts this NFA-e into an NFA by removing its epsilon transitions, then the states
+    /// that can no longer be reached.
+    ///
+    /// Every state is simplified with its full epsilon closure, whatever order the states are
+    /// in, so no epsilon transition is left for the conversion to fail on.
+    pub fn into_nfa(mut self) -> Result<NFA<L, S>, TransitionConversionError> {
         self.epsilon_simplify_all();
 
         self.remove_orphan_states();
 
-        NFA {
-            states: self
-                .states
-                .into_iter()
-                .map(|st| st.try_into().unwrap())
-                .collect(),
-            start: self.start,
-        }
+        NFA::try_from(self)
     }
 }
 
@@ -273,5 +697,523 @@ fn test_convert_to_nfa() {
 
     assert_eq!(nfae.get_states().len(), 3);
 
-    let _ = nfae.into_nfa();
+    nfae.into_nfa().unwrap();
+}
+
+#[test]
+fn test_nfae_to_dot() {
+    use crate::transition::RealTransition;
+
+    // the machine from `test_convert_to_nfa`
+    let mut builder = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let mut b = State::new(false, ());
+    let mut c = State::new(false, ());
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('b', 2));
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    b.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    c.add_transition(MaybeEpsilonTransition::new_epsilon(4));
+    builder
+        .add_state(start)
+        .add_state(a)
+        .add_state(b)
+        .add_state(c)
+        .add_state(State::new(true, ()));
+    builder.set_start(0);
+    let mut nfae = builder.build().unwrap();
+
+    let dot = nfae.to_dot();
+    assert!(dot.contains("1 -> 3 [label=\"ε\", style=dashed];"));
+    assert!(dot.contains("0 -> 1 [label=\"a\"];"));
+    assert!(dot.contains("4 [label=\"4\", shape=doublecircle];"));
+
+    nfae.epsilon_simplify_all();
+    assert!(!nfae.to_dot().contains("style=dashed"));
+}
+
+#[test]
+fn test_nfae_alphabet() {
+    use crate::transition::RealTransition;
+
+    // the machine from `test_convert_to_nfa`, where epsilon transitions add no symbol
+    let mut builder = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let mut b = State::new(false, ());
+    let mut c = State::new(false, ());
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('b', 2));
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    b.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    c.add_transition(MaybeEpsilonTransition::new_epsilon(4));
+    builder
+        .add_state(start)
+        .add_state(a)
+        .add_state(b)
+        .add_state(c)
+        .add_state(State::new(true, ()));
+    builder.set_start(0);
+    let nfae = builder.build().unwrap();
+
+    assert_eq!(nfae.alphabet(), HashSet::from([&'a', &'b']));
+}
+
+#[test]
+fn test_nfae_iter_states() {
+    use crate::transition::RealTransition;
+
+    // the machine from `test_convert_to_nfa`
+    let mut builder = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let mut b = State::new(false, ());
+    let mut c = State::new(false, ());
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('b', 2));
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    b.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    c.add_transition(MaybeEpsilonTransition::new_epsilon(4));
+    builder
+        .add_state(start)
+        .add_state(a)
+        .add_state(b)
+        .add_state(c)
+        .add_state(State::new(true, ()));
+    builder.set_start(0);
+    let nfae = builder.build().unwrap();
+
+    assert_eq!(nfae.iter_states().count(), 5);
+    assert_eq!(
+        nfae.iter_states()
+            .flat_map(|(_, st)| st.transitions.iter())
+            .filter(|tr| tr.is_epsilon())
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn test_epsilon_simplify_all_long_chain() {
+    // 0 -ε-> 1 -ε-> ... -ε-> n-1 -a-> n (finish)
+    let n = 500;
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    for i in 0..n - 1 {
+        let mut state = State::new(false, ());
+        state.add_transition(MaybeEpsilonTransition::new_epsilon(i + 1));
+        builder.add_state(state);
+    }
+    let mut last = State::new(false, ());
+    last.add_transition(MaybeEpsilonTransition::new_symbol('a', n));
+    builder.add_state(last).add_state(State::new(true, ()));
+    builder.set_start(0);
+
+    let mut nfae = builder.build().unwrap();
+    nfae.epsilon_simplify_all();
+
+    assert!(nfae
+        .iter_states()
+        .all(|(_, st)| st.transitions.iter().all(|tr| !tr.is_epsilon())));
+    assert!((0..n).all(|i| nfae.get_state(i).unwrap().transitions.len() == 1));
+
+    let nfa = nfae.into_nfa().unwrap();
+    assert_eq!(nfa.iter_states().count(), 2);
+    assert!(nfa.accepts(['a'].iter()));
+    assert!(nfa.traverse([].iter()).iter().all(|st| !st.is_finish()));
+
+    // the same chain with every epsilon edge pointing backward:
+    // n-1 -ε-> n-2 -ε-> ... -ε-> 0 -a-> n (finish)
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    let mut first = State::new(false, ());
+    first.add_transition(MaybeEpsilonTransition::new_symbol('a', n));
+    builder.add_state(first);
+    for i in 1..n {
+        let mut state = State::new(false, ());
+        state.add_transition(MaybeEpsilonTransition::new_epsilon(i - 1));
+        builder.add_state(state);
+    }
+    builder.add_state(State::new(true, ())).set_start(n - 1);
+
+    let mut nfae = builder.build().unwrap();
+    nfae.epsilon_simplify_all();
+    assert!((0..n).all(|i| nfae.get_state(i).unwrap().transitions.len() == 1));
+
+    let nfa = nfae.into_nfa().unwrap();
+    assert_eq!(nfa.iter_states().count(), 2);
+    assert!(nfa.accepts(['a'].iter()));
+    assert!(!nfa.accepts([].iter()));
+}
+
+#[test]
+fn test_into_nfa_backward_epsilon_chain() {
+    // epsilon edges point to earlier states and around a cycle, and state 5 is only entered
+    // through a symbol before following epsilons back into the chain:
+    // 0 -ε-> 3 -ε-> 1 -ε-> 2 -ε-> 0, 1 -a-> 4 (finish), 2 -b-> 5, 5 -ε-> 2
+    let mut states: Vec<State<(), MaybeEpsilonTransition<char>>> =
+        (0..6).map(|i| State::new(i == 4, ())).collect();
+    states[0].add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    states[3].add_transition(MaybeEpsilonTransition::new_epsilon(1));
+    states[1]
+        .add_transition(MaybeEpsilonTransition::new_epsilon(2))
+        .add_transition(MaybeEpsilonTransition::new_symbol('a', 4));
+    states[2]
+        .add_transition(MaybeEpsilonTransition::new_epsilon(0))
+        .add_transition(MaybeEpsilonTransition::new_symbol('b', 5));
+    states[5].add_transition(MaybeEpsilonTransition::new_epsilon(2));
+
+    let mut builder = NFAeBuilder::default();
+    for state in states {
+        builder.add_state(state);
+    }
+    builder.set_start(0);
+
+    let nfa = builder.build().unwrap().into_nfa().unwrap();
+    let accepts = |input: &str| nfa.accepts(input.chars().collect::<Vec<_>>().iter());
+    assert!(accepts("a"));
+    assert!(accepts("bba"));
+    assert!(!accepts("b"));
+    assert!(!accepts(""));
+    assert!(!accepts("ab"));
+}
+
+#[test]
+fn test_nfae_traverse() {
+    use crate::transition::RealTransition;
+
+    // the machine from `test_convert_to_nfa`, accepting "a" and "b"
+    let mut builder = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let mut b = State::new(false, ());
+    let mut c = State::new(false, ());
+    start
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('b', 2));
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    b.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    c.add_transition(MaybeEpsilonTransition::new_epsilon(4));
+    builder
+        .add_state(start)
+        .add_state(a)
+        .add_state(b)
+        .add_state(c)
+        .add_state(State::new(true, ()));
+    builder.set_start(0);
+    let nfae = builder.build().unwrap();
+
+    let inputs = ["", "a", "b", "ab", "c"];
+    let accepted: Vec<bool> = inputs
+        .iter()
+        .map(|input| {
+            let input: Vec<char> = input.chars().collect();
+            nfae.traverse(input.iter()).iter().any(|st| st.is_finish())
+        })
+        .collect();
+    assert_eq!(accepted, vec![false, true, true, false, false]);
+    assert_eq!(nfae.traverse(['a'].iter()).len(), 3);
+
+    // the machine is untouched, and agrees with its NFA
+    assert_eq!(nfae_accepts(nfae.clone(), &inputs), accepted);
+    assert_eq!(nfae.get_states().len(), 5);
+}
+
+#[test]
+fn test_epsilon_closure_cycle() {
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    let mut a = State::new(false, ());
+    let mut b = State::new(true, ());
+
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(1));
+    b.add_transition(MaybeEpsilonTransition::new_epsilon(0));
+
+    builder.add_state(a).add_state(b).set_start(0);
+
+    let nfae = builder.build().unwrap();
+
+    assert_eq!(nfae.epsilon_closure(0).len(), 2);
+    assert_eq!(nfae.epsilon_closure(1).len(), 2);
+
+    assert_eq!(nfae.epsilon_closure_indices(0), vec![0, 1]);
+    assert_eq!(nfae.epsilon_closure_indices(1), vec![1, 0]);
+}
+
+#[test]
+fn test_epsilon_closure_self_loop() {
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    let mut a = State::new(false, ());
+    let b = State::new(true, ());
+
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(0))
+        .add_transition(MaybeEpsilonTransition::new_epsilon(1));
+
+    builder.add_state(a).add_state(b).set_start(0);
+
+    let nfae = builder.build().unwrap();
+
+    assert_eq!(nfae.epsilon_closure(0).len(), 2);
+    assert_eq!(nfae.epsilon_closure_indices(0), vec![0, 1]);
+}
+
+#[test]
+fn test_dedupe_transitions() {
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    let mut a = State::new(false, ());
+    let b = State::new(true, ());
+
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(1))
+        .add_transition(MaybeEpsilonTransition::new_epsilon(1))
+        .add_transition(MaybeEpsilonTransition::new_symbol('a', 1))
+        .add_transition(MaybeEpsilonTransition::new_symbol('a', 1));
+
+    builder.add_state(a).add_state(b).set_start(0);
+
+    let mut nfae = builder.build().unwrap();
+    nfae.dedupe_transitions();
+
+    assert_eq!(nfae.get_start().transition_count(), 2);
+    assert_eq!(nfae_accepts(nfae, &["", "a", "aa"]), [true, true, false]);
+}
+
+#[test]
+fn test_epsilon_closure_diamond() {
+    let mut builder: NFAeBuilder<char, ()> = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    let mut a = State::new(false, ());
+    let mut b = State::new(false, ());
+    let finish = State::new(true, ());
+
+    start
+        .add_transition(MaybeEpsilonTransition::new_epsilon(1))
+        .add_transition(MaybeEpsilonTransition::new_epsilon(2));
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    b.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+
+    builder
+        .add_state(start)
+        .add_state(a)
+        .add_state(b)
+        .add_state(finish)
+        .set_start(0);
+
+    let nfae = builder.build().unwrap();
+
+    assert_eq!(nfae.epsilon_closure(0).len(), 4);
+
+    let mut indices = nfae.epsilon_closure_indices(0);
+    indices.sort_unstable();
+    assert_eq!(indices, vec![0, 1, 2, 3]);
+    assert!(nfae.epsilon_closure_indices(9).is_empty());
+}
+
+#[test]
+fn test_remove_orphan_states_transitive() {
+    use crate::transition::RealTransition;
+
+    let mut builder = NFAeBuilder::default();
+    let mut start = State::new(false, 0);
+    let mut a = State::new(false, 1);
+    let mut orphan = State::new(false, 2);
+    let mut b = State::new(false, 3);
+    let finish = State::new(true, 4);
+
+    start.add_transition(RealTransition::new('a', 1));
+    a.add_transition(MaybeEpsilonTransition::new_epsilon(3));
+    orphan.add_transition(RealTransition::new('x', 3));
+    b.add_transition(RealTransition::new('b', 4));
+
+    builder
+        .add_state(start)
+        .add_state(a)
+        .add_state(orphan)
+        .add_state(b)
+        .add_state(finish)
+        .set_start(0);
+
+    let mut nfae = builder.build().unwrap();
+
+    nfae.remove_orphan_states();
+
+    assert_eq!(
+        nfae.get_states()
+            .iter()
+            .map(|st| st.data)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 3, 4]
+    );
+    assert_eq!(nfae.get_state(1).unwrap().transitions[0].dest(), 2);
+    assert_eq!(nfae.get_state(2).unwrap().transitions[0].dest(), 3);
+}
+
+#[test]
+fn test_remove_orphan_states_remaps_start() {
+    use crate::transition::RealTransition;
+
+    let mut builder = NFAeBuilder::default();
+    let orphan = State::new(false, "orphan");
+    let mut start = State::new(false, "start");
+    let finish = State::new(true, "finish");
+
+    start.add_transition(RealTransition::new('a', 2));
+
+    builder
+        .add_state(orphan)
+        .add_state(start)
+        .add_state(finish)
+        .set_start(1);
+
+    let mut nfae = builder.build().unwrap();
+
+    nfae.remove_orphan_states();
+
+    assert_eq!(nfae.get_states().len(), 2);
+    assert_eq!(nfae.get_start().data, "start");
+}
+
+#[cfg(test)]
+fn symbol_nfae(symbol: char) -> NFAe<char, ()> {
+    use crate::transition::RealTransition;
+
+    let mut builder = NFAeBuilder::default();
+    let mut start = State::new(false, ());
+    let finish = State::new(true, ());
+
+    start.add_transition(RealTransition::new(symbol, 1));
+
+    builder.add_state(start).add_state(finish).set_start(0);
+
+    builder.build().unwrap()
+}
+
+#[cfg(test)]
+fn nfae_accepts(nfae: NFAe<char, ()>, inputs: &[&str]) -> Vec<bool> {
+    let nfa = nfae.into_nfa().unwrap();
+    inputs
+        .iter()
+        .map(|input| {
+            let input: Vec<char> = input.chars().collect();
+            nfa.accepts(input.iter())
+        })
+        .collect()
+}
+
+#[test]
+fn test_map_data() {
+    let nfae = symbol_nfae('a').star().map_data(|()| 7);
+
+    assert!(nfae.get_states().iter().all(|st| st.data == 7));
+    assert_eq!(nfae.epsilon_closure(nfae.start).len(), 2);
+}
+
+#[test]
+fn test_from_nfa() {
+    use crate::{nfa::NFABuilder, transition::RealTransition};
+
+    let nfa = symbol_nfae('a')
+        .concat(symbol_nfae('b'))
+        .into_nfa()
+        .unwrap();
+    let nfae = NFAe::from(nfa).star();
+    assert_eq!(
+        nfae_accepts(nfae, &["", "ab", "abab", "a", "aba"]),
+        [true, true, true, false, false]
+    );
+
+    // several starts get a shared new start
+    let mut builder = NFABuilder::default();
+    let mut first = State::new(false, ());
+    first.add_transition(RealTransition::new('a', 1));
+    builder
+        .add_state(first)
+        .add_state(State::new(true, ()))
+        .set_start(0)
+        .add_start(1);
+    let nfae = NFAe::from(builder.build().unwrap());
+    assert_eq!(nfae.get_states().len(), 3);
+    assert_eq!(nfae_accepts(nfae, &["", "a", "aa"]), [true, true, false]);
+}
+
+#[test]
+fn test_stats() {
+    let nfae = symbol_nfae('a').union(symbol_nfae('b'));
+    let stats = nfae.stats();
+    assert_eq!(
+        stats,
+        AutomatonStats {
+            num_states: 5,
+            num_transitions: 4,
+            num_epsilon_transitions: 2,
+            num_accepting: 2,
+            is_complete: false,
+            max_out_degree: 2,
+        }
+    );
+
+    let stats = nfae.into_nfa().unwrap().stats();
+    assert_eq!(stats.num_states, 3);
+    assert_eq!(stats.num_transitions, 2);
+    assert_eq!(stats.num_epsilon_transitions, 0);
+    assert_eq!(stats.num_accepting, 2);
+    assert_eq!(stats.max_out_degree, 2);
+}
+
+#[test]
+fn test_map_labels() {
+    let nfae = symbol_nfae('a')
+        .star()
+        .map_labels(|c| c.to_ascii_uppercase());
+
+    assert_eq!(
+        nfae_accepts(nfae, &["", "A", "AA", "a"]),
+        vec![true, true, true, false]
+    );
+}
+
+#[test]
+fn test_concat() {
+    assert_eq!(
+        nfae_accepts(
+            symbol_nfae('a').concat(symbol_nfae('b')),
+            &["ab", "a", "b", "ba", "abb"]
+        ),
+        vec![true, false, false, false, false]
+    );
+}
+
+#[test]
+fn test_union() {
+    assert_eq!(
+        nfae_accepts(
+            symbol_nfae('a').union(symbol_nfae('b')),
+            &["a", "b", "", "ab"]
+        ),
+        vec![true, true, false, false]
+    );
+}
+
+#[test]
+fn test_star() {
+    assert_eq!(
+        nfae_accepts(symbol_nfae('a').star(), &["", "a", "aaa", "b", "ab"]),
+        vec![true, true, true, false, false]
+    );
+}
+
+#[test]
+fn test_plus() {
+    assert_eq!(
+        nfae_accepts(symbol_nfae('a').plus(), &["", "a", "aaa", "b"]),
+        vec![false, true, true, false]
+    );
+}
+
+#[test]
+fn test_optional() {
+    assert_eq!(
+        nfae_accepts(symbol_nfae('a').optional(), &["", "a", "aa"]),
+        vec![true, true, false]
+    );
 }
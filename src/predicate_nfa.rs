@@ -0,0 +1,189 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{nfa::NFABuilderError, state::State, transition::PredicateTransition};
+
+#[derive(Default, Debug)]
+pub struct PredicateNFABuilder<L, S> {
+    states: Vec<State<S, PredicateTransition<L>>>,
+    starts: Vec<usize>,
+}
+
+impl<L, S> PredicateNFABuilder<L, S> {
+    pub fn add_state(&mut self, state: State<S, PredicateTransition<L>>) -> &mut Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Adds a state, returning its index.
+    pub fn add_state_indexed(&mut self, state: State<S, PredicateTransition<L>>) -> usize {
+        self.states.push(state);
+        self.states.len() - 1
+    }
+
+    /// Makes `start` the only start state.
+    pub fn set_start(&mut self, start: usize) -> &mut Self {
+        self.starts = vec![start];
+        self
+    }
+
+    /// Adds another start state.
+    pub fn add_start(&mut self, start: usize) -> &mut Self {
+        if !self.starts.contains(&start) {
+            self.starts.push(start);
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<PredicateNFA<L, S>, NFABuilderError> {
+        if self.starts.is_empty() {
+            return Err(NFABuilderError::MissingStartIndex);
+        }
+
+        if self.states.is_empty() {
+            return Err(NFABuilderError::MissingStates);
+        }
+
+        let finish_count = self.states.iter().filter(|&st| st.is_finish()).count();
+        if finish_count == 0 {
+            return Err(NFABuilderError::MissingFinish);
+        }
+
+        if self.starts.iter().any(|&start| start >= self.states.len()) {
+            return Err(NFABuilderError::InvalidStartIndex);
+        }
+
+        if let Some(dest) = self
+            .states
+            .iter()
+            .flat_map(|st| st.transitions.iter().map(|tr| tr.dest()))
+            .find(|&dest| dest >= self.states.len())
+        {
+            return Err(NFABuilderError::InvalidTransitionTarget(dest));
+        }
+
+        Ok(PredicateNFA {
+            starts: self.starts,
+            states: self.states,
+        })
+    }
+}
+
+/// An NFA whose transitions are [`PredicateTransition`]s, so each one is followed by every
+/// symbol satisfying its predicate.
+///
+/// The predicates are closures, so unlike [`NFA`](crate::nfa::NFA) this cannot be cloned,
+/// compared, serialized, or determinized, and can only be simulated.
+#[derive(Debug)]
+pub struct PredicateNFA<L, S> {
+    states: Vec<State<S, PredicateTransition<L>>>,
+    starts: Vec<usize>,
+}
+
+impl<L, S> PredicateNFA<L, S> {
+    pub fn get_state(&self, s: usize) -> Option<&State<S, PredicateTransition<L>>> {
+        self.states.get(s)
+    }
+
+    /// Gets the indices of every start state.
+    pub fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// Iterates over every state along with its index.
+    pub fn iter_states(&self) -> impl Iterator<Item = (usize, &State<S, PredicateTransition<L>>)> {
+        self.states.iter().enumerate()
+    }
+
+    /// Simulates this NFA on the given symbols from every start state.
+    /// Returns the distinct states that are reached after consuming every symbol.
+    pub fn traverse<'b, I>(&self, symbols: I) -> Vec<&State<S, PredicateTransition<L>>>
+    where
+        L: 'b,
+        I: Iterator<Item = &'b L>,
+    {
+        let mut frontier = self.starts.clone();
+        for symbol in symbols {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut next: Vec<usize> = frontier
+                .iter()
+                .flat_map(|&index| self.states[index].next(symbol))
+                .collect();
+            next.sort_unstable();
+            next.dedup();
+            frontier = next;
+        }
+
+        frontier
+            .into_iter()
+            .map(|index| &self.states[index])
+            .collect()
+    }
+
+    /// Returns whether some state reached from a start state on the given symbols is a finish.
+    pub fn accepts<'b, I>(&self, symbols: I) -> bool
+    where
+        L: 'b,
+        I: Iterator<Item = &'b L>,
+    {
+        self.traverse(symbols).iter().any(|st| st.is_finish())
+    }
+}
+
+#[test]
+fn test_predicate_nfa_accepts() {
+    // a digit, then letters until the finish
+    let mut builder = PredicateNFABuilder::default();
+    let mut digit = State::new(false, ());
+    digit.add_transition(PredicateTransition::new(|c: &char| c.is_ascii_digit(), 1));
+    let mut letters = State::new(true, ());
+    letters.add_transition(PredicateTransition::new(|c: &char| c.is_alphabetic(), 1));
+    builder.add_state(digit).add_state(letters).set_start(0);
+    let nfa = builder.build().unwrap();
+
+    let accepts = |input: &str| nfa.accepts(input.chars().collect::<Vec<_>>().iter());
+    assert!(accepts("5"));
+    assert!(accepts("5ab"));
+    assert!(!accepts("x"));
+    assert!(!accepts("55"));
+    assert!(!accepts(""));
+    assert_eq!(nfa.traverse([&'5'].into_iter()).len(), 1);
+}
+
+#[test]
+fn test_predicate_nfa_overlapping_predicates() {
+    // a digit either finishes or, if even, moves on to a second finish
+    let mut builder: PredicateNFABuilder<u32, &str> = PredicateNFABuilder::default();
+    let mut start = State::new(false, "start");
+    start
+        .add_transition(PredicateTransition::new(|n: &u32| *n < 10, 1))
+        .add_transition(PredicateTransition::new(|n: &u32| n % 2 == 0, 2));
+    builder
+        .add_state(start)
+        .add_state(State::new(true, "digit"))
+        .add_state(State::new(true, "even"))
+        .set_start(0);
+    let nfa = builder.build().unwrap();
+
+    let reached =
+        |n: u32| -> Vec<&str> { nfa.traverse([n].iter()).iter().map(|st| st.data).collect() };
+    assert_eq!(reached(4), vec!["digit", "even"]);
+    assert_eq!(reached(7), vec!["digit"]);
+    assert_eq!(reached(12), vec!["even"]);
+    assert!(reached(13).is_empty());
+}
+
+#[test]
+fn test_predicate_nfa_builder_invalid_target() {
+    let mut builder: PredicateNFABuilder<char, ()> = PredicateNFABuilder::default();
+    let mut start = State::new(true, ());
+    start.add_transition(PredicateTransition::new(|_: &char| true, 3));
+    builder.add_state(start).set_start(0);
+
+    assert!(matches!(
+        builder.build(),
+        Err(NFABuilderError::InvalidTransitionTarget(3))
+    ));
+}
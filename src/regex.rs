@@ -0,0 +1,651 @@
+use alloc::{
+    boxed::Box,
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::Display;
+
+use crate::{
+    nfa::{NFABuilder, NFA},
+    nfae::{NFAe, NFAeBuilder},
+    state::State,
+    transition::RealTransition,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum RegexError {
+    /// A parenthesis at the given position has no partner.
+    UnbalancedParenthesis(usize),
+    /// An operator or alternative at the given position has nothing to apply to.
+    MissingOperand(usize),
+    /// The pattern ends with an unfinished escape.
+    TrailingEscape,
+}
+
+impl Display for RegexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnbalancedParenthesis(pos) => write!(f, "unbalanced parenthesis at {pos}"),
+            Self::MissingOperand(pos) => write!(f, "missing operand at {pos}"),
+            Self::TrailingEscape => write!(f, "pattern must not end with an escape"),
+        }
+    }
+}
+
+/// A parsed regular expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegexAst {
+    /// Matches only the empty string.
+    Empty,
+    Literal(char),
+    Concat(Vec<RegexAst>),
+    Alternation(Vec<RegexAst>),
+    Star(Box<RegexAst>),
+    Plus(Box<RegexAst>),
+    Optional(Box<RegexAst>),
+}
+
+impl RegexAst {
+    /// Builds an NFA-e accepting exactly the strings matched by this expression.
+    pub fn to_nfae(&self) -> NFAe<char, ()> {
+        match self {
+            Self::Empty => {
+                let mut builder = NFAeBuilder::default();
+                builder.add_state(State::new(true, ())).set_start(0);
+                builder.build().unwrap()
+            }
+            Self::Literal(c) => {
+                let mut builder = NFAeBuilder::default();
+                let mut start = State::new(false, ());
+                start.add_transition(RealTransition::new(*c, 1));
+                builder
+                    .add_state(start)
+                    .add_state(State::new(true, ()))
+                    .set_start(0);
+                builder.build().unwrap()
+            }
+            Self::Concat(items) => items
+                .iter()
+                .map(RegexAst::to_nfae)
+                .reduce(NFAe::concat)
+                .unwrap_or_else(|| Self::Empty.to_nfae()),
+            Self::Alternation(branches) => branches
+                .iter()
+                .map(RegexAst::to_nfae)
+                .reduce(NFAe::union)
+                .unwrap_or_else(|| Self::Empty.to_nfae()),
+            Self::Star(inner) => inner.to_nfae().star(),
+            Self::Plus(inner) => inner.to_nfae().plus(),
+            Self::Optional(inner) => inner.to_nfae().optional(),
+        }
+    }
+}
+
+/// Parses a pattern supporting literals, concatenation, alternation `|`, the quantifiers
+/// `*`, `+` and `?`, grouping with parentheses, and `\` to escape any of these.
+pub fn parse(pattern: &str) -> Result<RegexAst, RegexError> {
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+
+    if parser.chars.is_empty() {
+        return Ok(RegexAst::Empty);
+    }
+
+    let ast = parser.parse_alternation()?;
+
+    // the only way to stop before the end is an unopened closing parenthesis
+    if parser.pos < parser.chars.len() {
+        return Err(RegexError::UnbalancedParenthesis(parser.pos));
+    }
+
+    Ok(ast)
+}
+
+/// Compiles a pattern into an NFA-e. See [`parse`] for the supported syntax.
+pub fn compile(pattern: &str) -> Result<NFAe<char, ()>, RegexError> {
+    Ok(parse(pattern)?.to_nfae())
+}
+
+/// Builds the position automaton of an expression, an NFA without epsilon transitions that
+/// has one state per literal plus the start state.
+///
+/// The state of each literal is entered by reading that literal, and is a finish if the
+/// literal can end a match. The start state is a finish if the expression matches the empty
+/// string.
+pub fn glushkov(ast: &RegexAst) -> NFA<char, ()> {
+    let mut symbols = Vec::new();
+    let mut follow = Vec::new();
+    let (nullable, first, last) = positions(ast, &mut symbols, &mut follow);
+
+    let mut builder = NFABuilder::default();
+
+    let mut start = State::new(nullable, ());
+    for &p in first.iter() {
+        start.add_transition(RealTransition::new(symbols[p - 1], p));
+    }
+    builder.add_state(start).set_start(0);
+
+    for (i, follow) in follow.into_iter().enumerate() {
+        let mut state = State::new(last.contains(&(i + 1)), ());
+        for q in follow {
+            state.add_transition(RealTransition::new(symbols[q - 1], q));
+        }
+        builder.add_state(state);
+    }
+
+    // every expression matches something, so some state is a finish
+    builder.build().unwrap()
+}
+
+/// Numbers the literals of `ast` from 1, recording each one's symbol and the positions that
+/// may follow it. Returns whether `ast` matches the empty string, and the positions that may
+/// begin and end a match.
+fn positions(
+    ast: &RegexAst,
+    symbols: &mut Vec<char>,
+    follow: &mut Vec<BTreeSet<usize>>,
+) -> (bool, BTreeSet<usize>, BTreeSet<usize>) {
+    match ast {
+        RegexAst::Empty => (true, BTreeSet::new(), BTreeSet::new()),
+        RegexAst::Literal(c) => {
+            symbols.push(*c);
+            follow.push(BTreeSet::new());
+            let p = symbols.len();
+            (false, BTreeSet::from([p]), BTreeSet::from([p]))
+        }
+        RegexAst::Concat(items) => {
+            let mut nullable = true;
+            let mut first = BTreeSet::new();
+            let mut last: BTreeSet<usize> = BTreeSet::new();
+            for item in items {
+                let (item_nullable, item_first, item_last) = positions(item, symbols, follow);
+                for &p in last.iter() {
+                    follow[p - 1].extend(item_first.iter().copied());
+                }
+                if nullable {
+                    first.extend(item_first);
+                }
+                if item_nullable {
+                    last.extend(item_last);
+                } else {
+                    last = item_last;
+                }
+                nullable &= item_nullable;
+            }
+            (nullable, first, last)
+        }
+        RegexAst::Alternation(branches) => {
+            let (mut nullable, mut first, mut last) =
+                (branches.is_empty(), BTreeSet::new(), BTreeSet::new());
+            for branch in branches {
+                let (branch_nullable, branch_first, branch_last) =
+                    positions(branch, symbols, follow);
+                nullable |= branch_nullable;
+                first.extend(branch_first);
+                last.extend(branch_last);
+            }
+            (nullable, first, last)
+        }
+        RegexAst::Star(inner) | RegexAst::Plus(inner) => {
+            let (nullable, first, last) = positions(inner, symbols, follow);
+            for &p in last.iter() {
+                follow[p - 1].extend(first.iter().copied());
+            }
+            (nullable || matches!(ast, RegexAst::Star(_)), first, last)
+        }
+        RegexAst::Optional(inner) => {
+            let (_, first, last) = positions(inner, symbols, follow);
+            (true, first, last)
+        }
+    }
+}
+
+/// An error for a `regex_syntax` expression that has no equivalent automaton in this crate.
+#[cfg(feature = "regex-syntax")]
+#[derive(Debug, PartialEq)]
+pub enum UnsupportedError {
+    /// An anchor, word boundary, or other look-around, which matches a position rather than
+    /// a symbol.
+    Look,
+    /// A class that matches no characters, or more than [`CLASS_LIMIT`] of them.
+    Class,
+    /// A literal or class of bytes that are not ASCII or valid UTF-8.
+    Bytes,
+}
+
+#[cfg(feature = "regex-syntax")]
+impl Display for UnsupportedError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Look => write!(f, "look-around assertions are not supported"),
+            Self::Class => write!(
+                f,
+                "classes must match between 1 and {CLASS_LIMIT} characters"
+            ),
+            Self::Bytes => write!(f, "bytes must be ASCII or valid UTF-8"),
+        }
+    }
+}
+
+/// The most characters a class may match, since each becomes its own transition.
+#[cfg(feature = "regex-syntax")]
+pub const CLASS_LIMIT: usize = 256;
+
+#[cfg(feature = "regex-syntax")]
+impl NFAe<char, ()> {
+    /// Builds an NFA-e accepting exactly the strings matched by an expression parsed with
+    /// `regex_syntax`. Capture groups are ignored and repetitions are expanded, so matching
+    /// is always greedy and over the whole input.
+    pub fn from_hir(hir: &regex_syntax::hir::Hir) -> Result<NFAe<char, ()>, UnsupportedError> {
+        Ok(hir_to_ast(hir)?.to_nfae())
+    }
+}
+
+#[cfg(feature = "regex-syntax")]
+fn hir_to_ast(hir: &regex_syntax::hir::Hir) -> Result<RegexAst, UnsupportedError> {
+    use regex_syntax::hir::{Class, HirKind};
+
+    Ok(match hir.kind() {
+        HirKind::Empty => RegexAst::Empty,
+        HirKind::Literal(literal) => {
+            let text = core::str::from_utf8(&literal.0).map_err(|_| UnsupportedError::Bytes)?;
+            RegexAst::Concat(text.chars().map(RegexAst::Literal).collect())
+        }
+        HirKind::Class(class) => {
+            let chars: Vec<char> = match class {
+                Class::Unicode(class) => class
+                    .ranges()
+                    .iter()
+                    .flat_map(|range| range.start()..=range.end())
+                    .take(CLASS_LIMIT + 1)
+                    .collect(),
+                Class::Bytes(class) => {
+                    if class.ranges().iter().any(|range| !range.end().is_ascii()) {
+                        return Err(UnsupportedError::Bytes);
+                    }
+                    class
+                        .ranges()
+                        .iter()
+                        .flat_map(|range| range.start()..=range.end())
+                        .map(char::from)
+                        .collect()
+                }
+            };
+            if chars.is_empty() || chars.len() > CLASS_LIMIT {
+                return Err(UnsupportedError::Class);
+            }
+            RegexAst::Alternation(chars.into_iter().map(RegexAst::Literal).collect())
+        }
+        HirKind::Look(_) => return Err(UnsupportedError::Look),
+        HirKind::Repetition(repetition) => {
+            let sub = hir_to_ast(&repetition.sub)?;
+            let min = repetition.min as usize;
+            let mut items = vec![sub.clone(); min];
+            match repetition.max {
+                // the last required copy can repeat instead of adding a starred copy
+                None => match items.pop() {
+                    Some(last) => items.push(RegexAst::Plus(Box::new(last))),
+                    None => items.push(RegexAst::Star(Box::new(sub))),
+                },
+                Some(max) => {
+                    let optional = RegexAst::Optional(Box::new(sub));
+                    items.extend(core::iter::repeat(optional).take(max as usize - min));
+                }
+            }
+            RegexAst::Concat(items)
+        }
+        HirKind::Capture(capture) => hir_to_ast(&capture.sub)?,
+        HirKind::Concat(subs) => {
+            RegexAst::Concat(subs.iter().map(hir_to_ast).collect::<Result<_, _>>()?)
+        }
+        HirKind::Alternation(subs) => {
+            RegexAst::Alternation(subs.iter().map(hir_to_ast).collect::<Result<_, _>>()?)
+        }
+    })
+}
+
+/// A regular expression over arbitrary displayed symbols, simplified as it is built and
+/// rendered in the syntax accepted by [`parse`].
+#[derive(Clone, PartialEq)]
+pub(crate) enum Expr {
+    Epsilon,
+    Symbol(String),
+    Concat(Vec<Expr>),
+    Alternation(Vec<Expr>),
+    Star(Box<Expr>),
+}
+
+impl Expr {
+    /// Creates a symbol, escaping any characters with a meaning in patterns.
+    pub(crate) fn symbol(symbol: impl Display) -> Self {
+        let mut escaped = String::new();
+        for c in symbol.to_string().chars() {
+            if matches!(c, '(' | ')' | '|' | '*' | '+' | '?' | '\\') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        Self::Symbol(escaped)
+    }
+
+    pub(crate) fn concat(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Epsilon, other) => other,
+            (this, Self::Epsilon) => this,
+            (Self::Concat(mut items), Self::Concat(more)) => {
+                items.extend(more);
+                Self::Concat(items)
+            }
+            (Self::Concat(mut items), other) => {
+                items.push(other);
+                Self::Concat(items)
+            }
+            (this, Self::Concat(mut items)) => {
+                items.insert(0, this);
+                Self::Concat(items)
+            }
+            (this, other) => Self::Concat(vec![this, other]),
+        }
+    }
+
+    pub(crate) fn alternate(self, other: Self) -> Self {
+        let mut branches = match self {
+            Self::Alternation(branches) => branches,
+            this => vec![this],
+        };
+        let more = match other {
+            Self::Alternation(branches) => branches,
+            other => vec![other],
+        };
+        for branch in more {
+            if !branches.contains(&branch) {
+                branches.push(branch);
+            }
+        }
+
+        if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Self::Alternation(branches)
+        }
+    }
+
+    pub(crate) fn star(self) -> Self {
+        match self {
+            Self::Epsilon => Self::Epsilon,
+            Self::Star(inner) => Self::Star(inner),
+            this => Self::Star(Box::new(this)),
+        }
+    }
+
+    /// Renders this expression. `level` is 0 at the top or inside a group, 1 inside a
+    /// concatenation, and 2 as the operand of a quantifier.
+    fn render(&self, level: u8, out: &mut String) {
+        match self {
+            Self::Epsilon => {}
+            Self::Symbol(symbol) => {
+                // an escape counts as a single character
+                let atomic = symbol.strip_prefix('\\').unwrap_or(symbol).chars().count() <= 1;
+                Self::group(level == 2 && !atomic, out, |out| out.push_str(symbol));
+            }
+            Self::Concat(items) => Self::group(level == 2, out, |out| {
+                for item in items {
+                    item.render(1, out);
+                }
+            }),
+            Self::Alternation(branches) if branches.contains(&Self::Epsilon) => {
+                let mut rest: Vec<Expr> = branches
+                    .iter()
+                    .filter(|&branch| *branch != Self::Epsilon)
+                    .cloned()
+                    .collect();
+                let rest = if rest.len() == 1 {
+                    rest.pop().unwrap()
+                } else {
+                    Self::Alternation(rest)
+                };
+                Self::group(level == 2, out, |out| {
+                    rest.render(2, out);
+                    out.push('?');
+                });
+            }
+            Self::Alternation(branches) => Self::group(level > 0, out, |out| {
+                for (i, branch) in branches.iter().enumerate() {
+                    if i > 0 {
+                        out.push('|');
+                    }
+                    branch.render(0, out);
+                }
+            }),
+            Self::Star(inner) => Self::group(level == 2, out, |out| {
+                inner.render(2, out);
+                out.push('*');
+            }),
+        }
+    }
+
+    fn group(parenthesize: bool, out: &mut String, f: impl FnOnce(&mut String)) {
+        if parenthesize {
+            out.push('(');
+        }
+        f(out);
+        if parenthesize {
+            out.push(')');
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut out = String::new();
+        self.render(0, &mut out);
+        write!(f, "{}", out)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse_alternation(&mut self) -> Result<RegexAst, RegexError> {
+        let mut branches = vec![self.parse_concat()?];
+
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            branches.push(self.parse_concat()?);
+        }
+
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            RegexAst::Alternation(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<RegexAst, RegexError> {
+        let mut items = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            items.push(self.parse_repeat()?);
+        }
+
+        if items.is_empty() {
+            return Err(if self.peek() == Some(')') && self.depth == 0 {
+                RegexError::UnbalancedParenthesis(self.pos)
+            } else {
+                RegexError::MissingOperand(self.pos)
+            });
+        }
+
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            RegexAst::Concat(items)
+        })
+    }
+
+    fn parse_repeat(&mut self) -> Result<RegexAst, RegexError> {
+        let mut ast = self.parse_atom()?;
+
+        while let Some(c) = self.peek() {
+            ast = match c {
+                '*' => RegexAst::Star(Box::new(ast)),
+                '+' => RegexAst::Plus(Box::new(ast)),
+                '?' => RegexAst::Optional(Box::new(ast)),
+                _ => break,
+            };
+            self.pos += 1;
+        }
+
+        Ok(ast)
+    }
+
+    fn parse_atom(&mut self) -> Result<RegexAst, RegexError> {
+        let start = self.pos;
+        let c = self.peek().unwrap();
+        self.pos += 1;
+
+        match c {
+            '(' => {
+                self.depth += 1;
+                let inner = self.parse_alternation()?;
+                if self.peek() != Some(')') {
+                    return Err(RegexError::UnbalancedParenthesis(start));
+                }
+                self.pos += 1;
+                self.depth -= 1;
+                Ok(inner)
+            }
+            '*' | '+' | '?' => Err(RegexError::MissingOperand(start)),
+            '\\' => {
+                let escaped = self.peek().ok_or(RegexError::TrailingEscape)?;
+                self.pos += 1;
+                Ok(RegexAst::Literal(escaped))
+            }
+            c => Ok(RegexAst::Literal(c)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn matches(pattern: &str, inputs: &[&str]) -> Vec<bool> {
+    let nfa = compile(pattern).unwrap().into_nfa().unwrap();
+    inputs
+        .iter()
+        .map(|input| {
+            let input: Vec<char> = input.chars().collect();
+            nfa.accepts(input.iter())
+        })
+        .collect()
+}
+
+#[test]
+fn test_compile() {
+    assert_eq!(
+        matches("a(b|c)*", &["a", "abbc", "acb", "b", "", "abd"]),
+        vec![true, true, true, false, false, false]
+    );
+    assert_eq!(
+        matches("ab+c?", &["ab", "abbb", "abc", "a", "ac", "abcc"]),
+        vec![true, true, true, false, false, false]
+    );
+    assert_eq!(
+        matches(r"\*\(|x", &["*(", "x", "*", ""]),
+        vec![true, true, false, false]
+    );
+    assert_eq!(matches("", &["", "a"]), vec![true, false]);
+}
+
+#[cfg(feature = "regex-syntax")]
+#[test]
+fn test_from_hir() {
+    let accepts = |pattern: &str, inputs: &[&str]| -> Vec<bool> {
+        let hir = regex_syntax::parse(pattern).unwrap();
+        let nfa = NFAe::from_hir(&hir).unwrap().into_nfa().unwrap();
+        inputs
+            .iter()
+            .map(|input| nfa.accepts(input.chars().collect::<Vec<_>>().iter()))
+            .collect()
+    };
+
+    assert_eq!(
+        accepts("a+b?", &["aab", "aa", "b", "", "abb"]),
+        [true, true, false, false, false]
+    );
+    assert_eq!(
+        accepts("(?:x|[0-9]){2,3}", &["x1", "12x", "x", "1234", "ab"]),
+        [true, true, false, false, false]
+    );
+    assert_eq!(
+        accepts("a{2,}|()", &["", "a", "aa", "aaaa"]),
+        [true, false, true, true]
+    );
+
+    let unsupported = |pattern| NFAe::from_hir(&regex_syntax::parse(pattern).unwrap()).err();
+    assert_eq!(unsupported("^a"), Some(UnsupportedError::Look));
+    assert_eq!(unsupported(r"a\b"), Some(UnsupportedError::Look));
+    assert_eq!(unsupported("."), Some(UnsupportedError::Class));
+}
+
+#[test]
+fn test_glushkov() {
+    let nfa = glushkov(&parse("(a|b)*abb").unwrap());
+    assert_eq!(nfa.iter_states().count(), 6);
+    assert!(nfa.accepts(['a', 'a', 'b', 'b'].iter()));
+    assert!(!nfa.accepts(['a', 'b', 'b', 'a'].iter()));
+
+    // agrees with the Thompson construction
+    for pattern in ["a(b|c)*", "ab+c?", "(ab)*c", "", "a?b?"] {
+        let nfa = glushkov(&parse(pattern).unwrap());
+        let inputs = ["", "a", "ab", "abc", "abbc", "c", "abcab", "ababc", "b"];
+        let expected = matches(pattern, &inputs);
+        let actual: Vec<bool> = inputs
+            .iter()
+            .map(|input| {
+                let input: Vec<char> = input.chars().collect();
+                nfa.accepts(input.iter())
+            })
+            .collect();
+        assert_eq!(actual, expected, "{}", pattern);
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        parse("a(b|c)*"),
+        Ok(RegexAst::Concat(vec![
+            RegexAst::Literal('a'),
+            RegexAst::Star(Box::new(RegexAst::Alternation(vec![
+                RegexAst::Literal('b'),
+                RegexAst::Literal('c'),
+            ]))),
+        ]))
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse("a(b"), Err(RegexError::UnbalancedParenthesis(1)));
+    assert_eq!(parse("ab)"), Err(RegexError::UnbalancedParenthesis(2)));
+    assert_eq!(parse(")"), Err(RegexError::UnbalancedParenthesis(0)));
+    assert_eq!(parse("*a"), Err(RegexError::MissingOperand(0)));
+    assert_eq!(parse("a|"), Err(RegexError::MissingOperand(2)));
+    assert_eq!(parse("()"), Err(RegexError::MissingOperand(1)));
+    assert_eq!(parse("a\\"), Err(RegexError::TrailingEscape));
+}
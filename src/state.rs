@@ -1,5 +1,11 @@
-use crate::transition::{MaybeEpsilonTransition, RealTransition};
+use alloc::vec::Vec;
 
+use crate::transition::{
+    MaybeEpsilonTransition, RealTransition, Transition, TransitionConversionError,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State<S, T> {
     /// Arbitrary user data held by this state.
     pub data: S,
@@ -10,7 +16,7 @@ pub struct State<S, T> {
 }
 
 impl<L, S> TryFrom<State<S, MaybeEpsilonTransition<L>>> for State<S, RealTransition<L>> {
-    type Error = <RealTransition<L> as TryFrom<MaybeEpsilonTransition<L>>>::Error;
+    type Error = TransitionConversionError;
 
     fn try_from(value: State<S, MaybeEpsilonTransition<L>>) -> Result<Self, Self::Error> {
         let transitions = {
@@ -39,21 +45,19 @@ impl<L, S> From<State<S, RealTransition<L>>> for State<S, MaybeEpsilonTransition
     }
 }
 
-impl<L, S> State<S, RealTransition<L>>
-where
-    L: PartialEq,
-{
-    /// Finds the state index that the given symbol transitions from this state to.
-    pub fn next(&self, symbol: &L) -> Vec<usize> {
+impl<S, T> State<S, T> {
+    /// Finds the state indices that the given symbol transitions from this state to.
+    pub fn next<L>(&self, symbol: &L) -> Vec<usize>
+    where
+        T: Transition<L>,
+    {
         self.transitions
             .iter()
-            .filter(|tr| tr.symbol() == symbol)
+            .filter(|tr| tr.matches(symbol))
             .map(|tr| tr.dest())
             .collect()
     }
-}
 
-impl<S, T> State<S, T> {
     /// Creates a new State with the given internal data.
     pub fn new(finish: bool, data: S) -> Self {
         State {
@@ -67,6 +71,16 @@ impl<S, T> State<S, T> {
         self.finish
     }
 
+    /// Gets the transitions from this state, in the order they were added.
+    pub fn transitions(&self) -> &[T] {
+        &self.transitions
+    }
+
+    /// Gets the number of transitions from this state.
+    pub fn transition_count(&self) -> usize {
+        self.transitions.len()
+    }
+
     pub fn add_transition(&mut self, transition: impl Into<T>) -> &mut Self {
         self.transitions.push(transition.into());
         self
@@ -80,4 +94,68 @@ impl<S, T> State<S, T> {
         self.transitions.extend(transitions.map(|tr| tr.into()));
         self
     }
+
+    /// Removes every transition matching the predicate, returning how many were removed.
+    pub fn remove_transitions(&mut self, pred: impl Fn(&T) -> bool) -> usize {
+        let len = self.transitions.len();
+        self.transitions.retain(|tr| !pred(tr));
+        len - self.transitions.len()
+    }
+
+    /// Transforms the data held by this state, keeping its transitions and finish flag.
+    pub fn map_data<U>(self, f: impl FnOnce(S) -> U) -> State<U, T> {
+        State {
+            data: f(self.data),
+            transitions: self.transitions,
+            finish: self.finish,
+        }
+    }
+}
+
+#[test]
+fn test_next_range() {
+    use crate::transition::RangeTransition;
+
+    let mut state: State<(), RangeTransition<char>> = State::new(false, ());
+    state
+        .add_transition(RangeTransition::new('a', 'z', 1))
+        .add_transition(RangeTransition::new('0', '9', 2));
+
+    assert_eq!(state.next(&'m'), vec![1]);
+    assert_eq!(state.next(&'a'), vec![1]);
+    assert_eq!(state.next(&'z'), vec![1]);
+    assert_eq!(state.next(&'0'), vec![2]);
+    assert!(state.next(&'A').is_empty());
+}
+
+#[test]
+fn test_next_predicate() {
+    use crate::transition::PredicateTransition;
+
+    let mut state: State<(), PredicateTransition<char>> = State::new(false, ());
+    state
+        .add_transition(PredicateTransition::new(|c: &char| c.is_ascii_digit(), 1))
+        .add_transition(PredicateTransition::new(|c: &char| *c == '7', 2));
+
+    assert_eq!(state.next(&'5'), vec![1]);
+    assert_eq!(state.next(&'7'), vec![1, 2]);
+    assert!(state.next(&'x').is_empty());
+    assert_eq!(
+        format!("{:?}", state.transitions[0]),
+        "PredicateTransition { dest: 1, .. }"
+    );
+}
+
+#[test]
+fn test_remove_transitions() {
+    let mut state: State<(), RealTransition<char>> = State::new(false, ());
+    state
+        .add_transition(RealTransition::new('a', 1))
+        .add_transition(RealTransition::new('b', 2))
+        .add_transition(RealTransition::new('c', 1));
+
+    assert_eq!(state.remove_transitions(|tr| tr.dest() == 1), 2);
+    assert_eq!(state.next(&'a'), Vec::<usize>::new());
+    assert_eq!(state.next(&'b'), vec![2]);
+    assert_eq!(state.remove_transitions(|tr| tr.dest() == 1), 0);
 }
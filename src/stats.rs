@@ -0,0 +1,16 @@
+/// Sizes and shape of an automaton, for checking the result of a sequence of transformations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AutomatonStats {
+    /// The number of states.
+    pub num_states: usize,
+    /// The number of transitions, including epsilon transitions.
+    pub num_transitions: usize,
+    /// The number of epsilon transitions, which only an NFA-e can have.
+    pub num_epsilon_transitions: usize,
+    /// The number of accepting states.
+    pub num_accepting: usize,
+    /// Whether every state has a transition on every symbol of the alphabet.
+    pub is_complete: bool,
+    /// The most transitions leaving any one state.
+    pub max_out_degree: usize,
+}
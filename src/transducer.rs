@@ -0,0 +1,376 @@
+use alloc::vec::Vec;
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    collections::{HashMap, RandomState},
+    dfa::{BuildError, DFA},
+    transition::FstTransition,
+};
+
+/// A DFA whose transitions may emit outputs, making it a deterministic finite-state
+/// transducer.
+///
+/// The outputs are kept beside the DFA, with one map per state from the symbols of its
+/// transitions to what they emit, so a transducer costs nothing for DFAs that do not need one.
+/// A transition without an entry emits nothing.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "L: serde::Serialize + Eq + Hash, S: serde::Serialize, O: serde::Serialize, \
+                     H: BuildHasher"
+    ))
+)]
+pub struct Transducer<L, S, O, H = RandomState> {
+    dfa: DFA<L, S, H>,
+    outputs: Vec<HashMap<L, O, H>>,
+}
+
+/// The fields of a serialized transducer, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(
+    deserialize = "L: serde::Deserialize<'de> + Eq + Hash, S: serde::Deserialize<'de>, \
+                   O: serde::Deserialize<'de>, H: BuildHasher + Default"
+))]
+struct RawTransducer<L, S, O, H> {
+    dfa: DFA<L, S, H>,
+    outputs: Vec<HashMap<L, O, H>>,
+}
+
+/// Deserializing fails unless the DFA is valid, there is an output map for every state, and
+/// every output belongs to a transition.
+#[cfg(feature = "serde")]
+impl<'de, L, S, O, H> serde::Deserialize<'de> for Transducer<L, S, O, H>
+where
+    L: serde::Deserialize<'de> + Eq + Hash,
+    S: serde::Deserialize<'de>,
+    O: serde::Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawTransducer { dfa, outputs } = RawTransducer::deserialize(deserializer)?;
+
+        if outputs.len() != dfa.values.len() {
+            return Err(serde::de::Error::custom(
+                BuildError::MismatchedTransitions {
+                    states: dfa.values.len(),
+                    maps: outputs.len(),
+                },
+            ));
+        }
+        if let Some(state) = (0..outputs.len()).find(|&state| {
+            outputs[state]
+                .keys()
+                .any(|symbol| !dfa.transitions[state].contains_key(symbol))
+        }) {
+            return Err(serde::de::Error::custom(BuildError::MismatchedAnnotations(
+                state,
+            )));
+        }
+
+        Ok(Transducer { dfa, outputs })
+    }
+}
+
+impl<L, S, O> Transducer<L, S, O>
+where
+    L: Clone + Eq + Hash,
+{
+    /// Assembles a transducer from the value of every state and a list of transitions, each
+    /// leaving the state it is paired with, like [`DFA::from_parts`]. Repeating a transition
+    /// is allowed as long as it emits the same output.
+    pub fn from_parts(
+        values: Vec<S>,
+        transitions: Vec<(usize, FstTransition<L, O>)>,
+        start: usize,
+    ) -> Result<Self, BuildError>
+    where
+        O: PartialEq,
+    {
+        let mut outputs: Vec<HashMap<L, O>> = (0..values.len()).map(|_| HashMap::new()).collect();
+        let mut edges = Vec::with_capacity(transitions.len());
+        let mut emitted = Vec::new();
+        for (from, transition) in transitions {
+            edges.push((from, transition.symbol.clone(), transition.dest));
+            if let Some(output) = transition.output {
+                emitted.push((from, transition.symbol, output));
+            }
+        }
+
+        let dfa = DFA::from_parts(values, edges, start)?;
+        for (from, symbol, output) in emitted {
+            match outputs[from].get(&symbol) {
+                Some(existing) if *existing != output => {
+                    return Err(BuildError::MismatchedAnnotations(from));
+                }
+                Some(_) => {}
+                None => {
+                    outputs[from].insert(symbol, output);
+                }
+            }
+        }
+
+        Ok(Transducer { dfa, outputs })
+    }
+}
+
+impl<L, S, O, H> Transducer<L, S, O, H>
+where
+    H: Default,
+{
+    /// Wraps a DFA whose transitions do not emit anything yet.
+    pub fn new(dfa: DFA<L, S, H>) -> Self {
+        let outputs = (0..dfa.values.len()).map(|_| HashMap::default()).collect();
+        Transducer { dfa, outputs }
+    }
+}
+
+impl<L, S, O, H> Transducer<L, S, O, H> {
+    /// Gets the DFA whose transitions this transducer follows.
+    pub fn dfa(&self) -> &DFA<L, S, H> {
+        &self.dfa
+    }
+
+    /// Unwraps the DFA, dropping the outputs.
+    pub fn into_dfa(self) -> DFA<L, S, H> {
+        self.dfa
+    }
+}
+
+impl<L, S, O, H> Transducer<L, S, O, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Gets the output of the transition on `symbol` out of state `from`, if it emits one.
+    pub fn output(&self, from: usize, symbol: &L) -> Option<&O> {
+        self.outputs.get(from)?.get(symbol)
+    }
+
+    /// Makes the transition on `symbol` out of state `from` emit `output`, replacing what it
+    /// emitted before. Returns false, changing nothing, if there is no such transition.
+    pub fn set_output(&mut self, from: usize, symbol: L, output: O) -> bool {
+        let exists = self
+            .dfa
+            .transitions
+            .get(from)
+            .is_some_and(|transitions| transitions.contains_key(&symbol));
+        if exists {
+            self.outputs[from].insert(symbol, output);
+        }
+        exists
+    }
+
+    /// Stops the transition on `symbol` out of state `from` from emitting anything, returning
+    /// what it emitted.
+    pub fn remove_output(&mut self, from: usize, symbol: &L) -> Option<O> {
+        self.outputs.get_mut(from)?.remove(symbol)
+    }
+
+    /// Follows the transitions for each input from the start state, collecting the outputs
+    /// of the ones that emit something. Returns `None` if some input has no transition.
+    pub fn run<I>(&self, inputs: I) -> Option<Vec<O>>
+    where
+        I: Iterator,
+        I::Item: Borrow<L>,
+        O: Clone,
+    {
+        let mut emitted = Vec::new();
+        let mut curr = self.dfa.start;
+        for input in inputs {
+            let input = input.borrow();
+            let next = *self.dfa.transitions[curr].get(input)?;
+            if next >= self.dfa.values.len() {
+                return None;
+            }
+            emitted.extend(self.outputs[curr].get(input).cloned());
+            curr = next;
+        }
+        Some(emitted)
+    }
+
+    /// Merges equivalent states like [`DFA::minimize`], where states are only equivalent if
+    /// their transitions also emit the same outputs.
+    pub fn minimize<A, M>(self, is_accepting: A, merge: M) -> Self
+    where
+        A: Fn(&S) -> bool,
+        M: FnMut(Vec<S>) -> S,
+        L: Clone,
+        O: Clone + Eq + Hash,
+        H: Default,
+    {
+        // pair every symbol with its output, so that the output is part of the transition
+        let Transducer { dfa, outputs } = self;
+        let labeled: DFA<(L, Option<O>), S, H> = DFA {
+            values: dfa.values,
+            transitions: dfa
+                .transitions
+                .into_iter()
+                .zip(&outputs)
+                .map(|(transitions, outputs)| {
+                    transitions
+                        .into_iter()
+                        .map(|(symbol, dest)| {
+                            let output = outputs.get(&symbol).cloned();
+                            ((symbol, output), dest)
+                        })
+                        .collect()
+                })
+                .collect(),
+            start: dfa.start,
+        };
+
+        let minimal = labeled.minimize(is_accepting, merge);
+        let mut transitions = Vec::with_capacity(minimal.values.len());
+        let mut outputs = Vec::with_capacity(minimal.values.len());
+        for labeled in minimal.transitions {
+            let mut state_transitions = HashMap::default();
+            let mut state_outputs = HashMap::default();
+            for ((symbol, output), dest) in labeled {
+                if let Some(output) = output {
+                    state_outputs.insert(symbol.clone(), output);
+                }
+                state_transitions.insert(symbol, dest);
+            }
+            transitions.push(state_transitions);
+            outputs.push(state_outputs);
+        }
+
+        Transducer {
+            dfa: DFA {
+                values: minimal.values,
+                transitions,
+                start: minimal.start,
+            },
+            outputs,
+        }
+    }
+}
+
+#[test]
+fn test_run() {
+    // each state remembers whether the previous symbol was an 'a'
+    let mut dfa = DFA::new(false);
+    dfa.add_state(true);
+    for state in 0..2 {
+        dfa.get_state_mut(state)
+            .unwrap()
+            .set_transition('a', 1)
+            .set_transition('b', 0);
+    }
+
+    let mut transducer = Transducer::new(dfa);
+    for state in 0..2 {
+        assert!(transducer.set_output(state, 'a', 1));
+        assert!(transducer.set_output(state, 'b', 2));
+    }
+    assert!(!transducer.set_output(0, 'c', 3));
+    assert!(!transducer.set_output(2, 'a', 3));
+
+    assert_eq!(transducer.run("abba".chars()), Some(vec![1, 2, 2, 1]));
+    assert_eq!(transducer.run("abc".chars()), None);
+    assert_eq!(transducer.run("".chars()), Some(vec![]));
+
+    // only a 'b' that follows an 'a' keeps its output
+    assert_eq!(transducer.remove_output(0, &'b'), Some(2));
+    assert_eq!(transducer.remove_output(0, &'b'), None);
+    assert_eq!(transducer.output(1, &'b'), Some(&2));
+    assert_eq!(transducer.run("abbab".chars()), Some(vec![1, 2, 1, 2]));
+}
+
+#[test]
+fn test_from_parts() {
+    // alternates outputs on the same symbol between two states with equal values
+    let parity = Transducer::from_parts(
+        vec![(), ()],
+        vec![
+            (0, FstTransition::new('x', Some("odd"), 1)),
+            (1, FstTransition::new('x', Some("even"), 0)),
+            (1, FstTransition::new('y', None, 1)),
+        ],
+        0,
+    )
+    .unwrap();
+    assert_eq!(parity.run("xyxx".chars()), Some(vec!["odd", "even", "odd"]));
+    assert_eq!(parity.output(1, &'y'), None);
+
+    let conflicting = Transducer::from_parts(
+        vec![(), ()],
+        vec![
+            (0, FstTransition::new('x', Some(1), 1)),
+            (0, FstTransition::new('x', Some(2), 1)),
+        ],
+        0,
+    );
+    assert_eq!(
+        conflicting.unwrap_err(),
+        BuildError::MismatchedAnnotations(0)
+    );
+
+    let dangling = Transducer::<char, (), u8>::from_parts(
+        vec![()],
+        vec![(0, FstTransition::new('x', None, 4))],
+        0,
+    );
+    assert_eq!(
+        dangling.unwrap_err(),
+        BuildError::InvalidTransitionTarget(4)
+    );
+}
+
+#[test]
+fn test_minimize() {
+    // a loop of four states on 'x' emitting 0, 1, 0, 1, which only needs two
+    let looping = Transducer::from_parts(
+        vec![true; 4],
+        (0..4)
+            .map(|i| (i, FstTransition::new('x', Some(i % 2), (i + 1) % 4)))
+            .collect(),
+        0,
+    )
+    .unwrap();
+    let minimal = looping.clone().minimize(|&v| v, |values| values[0]);
+    assert_eq!(minimal.dfa().state_count(), 2);
+    assert_eq!(minimal.run("xxxxx".chars()), Some(vec![0, 1, 0, 1, 0]));
+
+    // when every transition emits the same output, one state is enough
+    let mut same = looping;
+    for i in 0..4 {
+        same.set_output(i, 'x', 7);
+    }
+    let minimal = same.minimize(|&v| v, |values| values[0]);
+    assert_eq!(minimal.dfa().state_count(), 1);
+    assert_eq!(minimal.run("xx".chars()), Some(vec![7, 7]));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_transducer_serde_round_trip() {
+    let transducer = Transducer::from_parts(
+        vec![false, true],
+        vec![
+            (0, FstTransition::new('a', Some(1), 1)),
+            (1, FstTransition::new('b', None, 0)),
+        ],
+        0,
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&transducer).unwrap();
+    let back: Transducer<char, bool, i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.run("aba".chars()), Some(vec![1, 1]));
+
+    let dfa = r#"{"values":[false],"transitions":[{"a":0}],"start":0}"#;
+    for outputs in [r#"[]"#, r#"[{"b":1}]"#] {
+        let json = format!(r#"{{"dfa":{},"outputs":{}}}"#, dfa, outputs);
+        assert!(serde_json::from_str::<Transducer<char, bool, i32>>(&json).is_err());
+    }
+    let json = format!(r#"{{"dfa":{},"outputs":[{{"a":1}}]}}"#, dfa);
+    let back: Transducer<char, bool, i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.run("aa".chars()), Some(vec![1, 1]));
+}
@@ -1,3 +1,17 @@
+use alloc::boxed::Box;
+use core::fmt::Display;
+
+/// A transition that some symbols follow to a destination state.
+pub trait Transition<L> {
+    /// Returns true if the given symbol follows this transition.
+    fn matches(&self, symbol: &L) -> bool;
+
+    /// Gets the destination of this transition.
+    fn dest(&self) -> usize;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RealTransition<L> {
     pub symbol: L,
     pub dest: usize,
@@ -19,15 +33,51 @@ impl<L> RealTransition<L> {
     pub fn dest(&self) -> usize {
         self.dest
     }
+
+    /// Transforms the symbol of this transition.
+    pub fn map_symbol<M>(self, f: impl FnOnce(L) -> M) -> RealTransition<M> {
+        RealTransition::new(f(self.symbol), self.dest)
+    }
+}
+
+impl<L> Transition<L> for RealTransition<L>
+where
+    L: PartialEq,
+{
+    fn matches(&self, symbol: &L) -> bool {
+        self.symbol == *symbol
+    }
+
+    fn dest(&self) -> usize {
+        self.dest
+    }
 }
 
+/// An error for converting a transition into one that must have a symbol.
+#[derive(Debug, PartialEq)]
+pub enum TransitionConversionError {
+    /// The transition is an epsilon transition.
+    EpsilonNotAllowed,
+}
+
+impl Display for TransitionConversionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::EpsilonNotAllowed => write!(f, "transition must have a symbol"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransitionConversionError {}
+
 impl<L> TryFrom<MaybeEpsilonTransition<L>> for RealTransition<L> {
-    type Error = String;
+    type Error = TransitionConversionError;
 
     fn try_from(value: MaybeEpsilonTransition<L>) -> Result<Self, Self::Error> {
         let symbol = match value.kind {
             MaybeEpsilonTransitionKind::Epsilon => {
-                return Err("transition must have a symbol".into())
+                return Err(TransitionConversionError::EpsilonNotAllowed)
             }
             MaybeEpsilonTransitionKind::Symbol(sym) => sym,
         };
@@ -39,7 +89,8 @@ impl<L> TryFrom<MaybeEpsilonTransition<L>> for RealTransition<L> {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MaybeEpsilonTransition<L> {
     pub kind: MaybeEpsilonTransitionKind<L>,
     pub dest: usize,
@@ -82,6 +133,33 @@ impl<L> MaybeEpsilonTransition<L> {
     pub fn is_epsilon(&self) -> bool {
         matches!(self.kind, MaybeEpsilonTransitionKind::Epsilon)
     }
+
+    /// Transforms the symbol of this transition. Epsilon transitions stay epsilon transitions.
+    pub fn map_symbol<M>(self, f: impl FnOnce(L) -> M) -> MaybeEpsilonTransition<M> {
+        MaybeEpsilonTransition {
+            kind: match self.kind {
+                MaybeEpsilonTransitionKind::Epsilon => MaybeEpsilonTransitionKind::Epsilon,
+                MaybeEpsilonTransitionKind::Symbol(symbol) => {
+                    MaybeEpsilonTransitionKind::Symbol(f(symbol))
+                }
+            },
+            dest: self.dest,
+        }
+    }
+}
+
+impl<L> Transition<L> for MaybeEpsilonTransition<L>
+where
+    L: PartialEq,
+{
+    /// Epsilon transitions never match a symbol.
+    fn matches(&self, symbol: &L) -> bool {
+        self.symbol() == Some(symbol)
+    }
+
+    fn dest(&self) -> usize {
+        self.dest
+    }
 }
 
 impl<L> From<RealTransition<L>> for MaybeEpsilonTransition<L> {
@@ -93,7 +171,8 @@ impl<L> From<RealTransition<L>> for MaybeEpsilonTransition<L> {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MaybeEpsilonTransitionKind<L> {
     Epsilon,
     Symbol(L),
@@ -107,3 +186,190 @@ impl<L> MaybeEpsilonTransitionKind<L> {
         }
     }
 }
+
+/// A transition followed by every symbol in an inclusive range.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeTransition<L> {
+    pub lo: L,
+    pub hi: L,
+    pub dest: usize,
+}
+
+impl<L> RangeTransition<L> {
+    pub fn new(lo: L, hi: L, dest: usize) -> Self {
+        RangeTransition { lo, hi, dest }
+    }
+
+    /// Gets the destination of this transition.
+    #[inline]
+    pub fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+impl<L> RangeTransition<L>
+where
+    L: Ord,
+{
+    /// Returns true if the symbol lies within this transition's range.
+    pub fn contains(&self, symbol: &L) -> bool {
+        self.lo <= *symbol && *symbol <= self.hi
+    }
+}
+
+impl<L> Transition<L> for RangeTransition<L>
+where
+    L: Ord,
+{
+    fn matches(&self, symbol: &L) -> bool {
+        self.contains(symbol)
+    }
+
+    fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+/// A transition followed by every symbol that satisfies a predicate, such as a character class.
+///
+/// The predicate is an arbitrary closure, so unlike the other transitions this one cannot be
+/// cloned, compared, or serialized, and only a [`PredicateNFA`](crate::predicate_nfa::PredicateNFA)
+/// holds it.
+pub struct PredicateTransition<L> {
+    predicate: Box<dyn Fn(&L) -> bool>,
+    pub dest: usize,
+}
+
+impl<L> PredicateTransition<L> {
+    pub fn new(predicate: impl Fn(&L) -> bool + 'static, dest: usize) -> Self {
+        PredicateTransition {
+            predicate: Box::new(predicate),
+            dest,
+        }
+    }
+
+    /// Gets the destination of this transition.
+    #[inline]
+    pub fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+impl<L> core::fmt::Debug for PredicateTransition<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PredicateTransition")
+            .field("dest", &self.dest)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L> Transition<L> for PredicateTransition<L> {
+    fn matches(&self, symbol: &L) -> bool {
+        (self.predicate)(symbol)
+    }
+
+    fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+/// A transition that may emit an output when it is followed, as in a finite-state transducer.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FstTransition<L, O> {
+    pub symbol: L,
+    pub output: Option<O>,
+    pub dest: usize,
+}
+
+impl<L, O> FstTransition<L, O> {
+    pub fn new(symbol: L, output: Option<O>, dest: usize) -> Self {
+        FstTransition {
+            symbol,
+            output,
+            dest,
+        }
+    }
+
+    /// Gets the symbol associated with this transition.
+    #[inline]
+    pub fn symbol(&self) -> &L {
+        &self.symbol
+    }
+
+    /// Gets the output emitted by following this transition, if any.
+    #[inline]
+    pub fn output(&self) -> Option<&O> {
+        self.output.as_ref()
+    }
+
+    /// Gets the destination of this transition.
+    #[inline]
+    pub fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+impl<L, O> Transition<L> for FstTransition<L, O>
+where
+    L: PartialEq,
+{
+    fn matches(&self, symbol: &L) -> bool {
+        self.symbol == *symbol
+    }
+
+    fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+/// A transition that costs a weight to follow, as in a weighted automaton.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedTransition<L> {
+    pub symbol: L,
+    pub weight: u64,
+    pub dest: usize,
+}
+
+impl<L> WeightedTransition<L> {
+    pub fn new(symbol: L, weight: u64, dest: usize) -> Self {
+        WeightedTransition {
+            symbol,
+            weight,
+            dest,
+        }
+    }
+
+    /// Gets the symbol associated with this transition.
+    #[inline]
+    pub fn symbol(&self) -> &L {
+        &self.symbol
+    }
+
+    /// Gets the cost of following this transition.
+    #[inline]
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    /// Gets the destination of this transition.
+    #[inline]
+    pub fn dest(&self) -> usize {
+        self.dest
+    }
+}
+
+impl<L> Transition<L> for WeightedTransition<L>
+where
+    L: PartialEq,
+{
+    fn matches(&self, symbol: &L) -> bool {
+        self.symbol == *symbol
+    }
+
+    fn dest(&self) -> usize {
+        self.dest
+    }
+}
@@ -0,0 +1,305 @@
+use alloc::{collections::BinaryHeap, vec, vec::Vec};
+use core::{
+    cmp::Reverse,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    collections::{HashMap, RandomState},
+    dfa::{BuildError, DFA},
+    transition::WeightedTransition,
+};
+
+/// A DFA whose every transition has a weight, for finding the cheapest accepted string.
+///
+/// The weights are kept beside the DFA, with one map per state from the symbols of its
+/// transitions to what they cost, so unweighted DFAs are unaffected.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "L: serde::Serialize + Eq + Hash, S: serde::Serialize, H: BuildHasher"
+    ))
+)]
+pub struct WeightedDFA<L, S, H = RandomState> {
+    dfa: DFA<L, S, H>,
+    weights: Vec<HashMap<L, u64, H>>,
+}
+
+/// The fields of a serialized weighted DFA, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(
+    deserialize = "L: serde::Deserialize<'de> + Eq + Hash, S: serde::Deserialize<'de>, \
+                   H: BuildHasher + Default"
+))]
+struct RawWeightedDFA<L, S, H> {
+    dfa: DFA<L, S, H>,
+    weights: Vec<HashMap<L, u64, H>>,
+}
+
+/// Deserializing fails unless the DFA is valid, there is a weight map for every state, and
+/// every transition has exactly one weight.
+#[cfg(feature = "serde")]
+impl<'de, L, S, H> serde::Deserialize<'de> for WeightedDFA<L, S, H>
+where
+    L: serde::Deserialize<'de> + Eq + Hash,
+    S: serde::Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawWeightedDFA { dfa, weights } = RawWeightedDFA::deserialize(deserializer)?;
+
+        if weights.len() != dfa.values.len() {
+            return Err(serde::de::Error::custom(
+                BuildError::MismatchedTransitions {
+                    states: dfa.values.len(),
+                    maps: weights.len(),
+                },
+            ));
+        }
+        if let Some(state) = (0..weights.len()).find(|&state| {
+            let transitions = &dfa.transitions[state];
+            weights[state].len() != transitions.len()
+                || weights[state]
+                    .keys()
+                    .any(|symbol| !transitions.contains_key(symbol))
+        }) {
+            return Err(serde::de::Error::custom(BuildError::MismatchedAnnotations(
+                state,
+            )));
+        }
+
+        Ok(WeightedDFA { dfa, weights })
+    }
+}
+
+impl<L, S> WeightedDFA<L, S>
+where
+    L: Clone + Eq + Hash,
+{
+    /// Assembles a weighted DFA from the value of every state and a list of transitions, each
+    /// leaving the state it is paired with, like [`DFA::from_parts`]. Repeating a transition
+    /// is allowed as long as it has the same weight.
+    pub fn from_parts(
+        values: Vec<S>,
+        transitions: Vec<(usize, WeightedTransition<L>)>,
+        start: usize,
+    ) -> Result<Self, BuildError> {
+        let mut weights: Vec<HashMap<L, u64>> = (0..values.len()).map(|_| HashMap::new()).collect();
+        let edges = transitions
+            .iter()
+            .map(|(from, transition)| (*from, transition.symbol.clone(), transition.dest))
+            .collect();
+
+        let dfa = DFA::from_parts(values, edges, start)?;
+        for (from, transition) in transitions {
+            if *weights[from]
+                .entry(transition.symbol)
+                .or_insert(transition.weight)
+                != transition.weight
+            {
+                return Err(BuildError::MismatchedAnnotations(from));
+            }
+        }
+
+        Ok(WeightedDFA { dfa, weights })
+    }
+}
+
+impl<L, S, H> WeightedDFA<L, S, H>
+where
+    L: Clone + Eq + Hash,
+    H: BuildHasher + Default,
+{
+    /// Wraps a DFA, giving every one of its transitions the same weight.
+    pub fn new(dfa: DFA<L, S, H>, weight: u64) -> Self {
+        let weights = dfa
+            .transitions
+            .iter()
+            .map(|transitions| {
+                transitions
+                    .keys()
+                    .map(|symbol| (symbol.clone(), weight))
+                    .collect()
+            })
+            .collect();
+        WeightedDFA { dfa, weights }
+    }
+}
+
+impl<L, S, H> WeightedDFA<L, S, H> {
+    /// Gets the DFA whose transitions this weighs.
+    pub fn dfa(&self) -> &DFA<L, S, H> {
+        &self.dfa
+    }
+
+    /// Unwraps the DFA, dropping the weights.
+    pub fn into_dfa(self) -> DFA<L, S, H> {
+        self.dfa
+    }
+}
+
+impl<L, S, H> WeightedDFA<L, S, H>
+where
+    L: Eq + Hash,
+    H: BuildHasher,
+{
+    /// Gets the weight of the transition on `symbol` out of state `from`, or `None` if there
+    /// is no such transition.
+    pub fn weight(&self, from: usize, symbol: &L) -> Option<u64> {
+        self.weights.get(from)?.get(symbol).copied()
+    }
+
+    /// Sets the weight of the transition on `symbol` out of state `from`. Returns false,
+    /// changing nothing, if there is no such transition.
+    pub fn set_weight(&mut self, from: usize, symbol: &L, weight: u64) -> bool {
+        match self
+            .weights
+            .get_mut(from)
+            .and_then(|weights| weights.get_mut(symbol))
+        {
+            Some(known) => {
+                *known = weight;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Finds a string of least total weight leading from the start to a state satisfying
+    /// `is_accepting`, along with its weight, using Dijkstra's algorithm. Totals saturate at
+    /// `u64::MAX`. Returns `None` if no accepting state is reachable.
+    pub fn lightest_accepted(&self, is_accepting: impl Fn(&S) -> bool) -> Option<(u64, Vec<L>)>
+    where
+        L: Clone,
+    {
+        let count = self.dfa.values.len();
+        let mut distances: Vec<Option<u64>> = vec![None; count];
+        let mut predecessors: Vec<Option<(usize, &L)>> = vec![None; count];
+        let mut heap = BinaryHeap::from([Reverse((0, self.dfa.start))]);
+        distances[self.dfa.start] = Some(0);
+
+        while let Some(Reverse((distance, state))) = heap.pop() {
+            if distances[state] != Some(distance) {
+                continue;
+            }
+
+            if is_accepting(&self.dfa.values[state]) {
+                let mut path = Vec::new();
+                let mut curr = state;
+                while let Some((prev, symbol)) = predecessors[curr] {
+                    path.push(symbol.clone());
+                    curr = prev;
+                }
+                path.reverse();
+                return Some((distance, path));
+            }
+
+            for (symbol, &dest) in self.dfa.transitions[state].iter() {
+                if dest >= count {
+                    continue;
+                }
+                let through = distance.saturating_add(self.weights[state][symbol]);
+                if distances[dest].map_or(true, |known| through < known) {
+                    distances[dest] = Some(through);
+                    predecessors[dest] = Some((state, symbol));
+                    heap.push(Reverse((through, dest)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[test]
+fn test_lightest_accepted() {
+    // "ab" and "c" both reach the accepting state 3, but "c" is heavier
+    let mut weighted = WeightedDFA::from_parts(
+        vec![false, false, false, true],
+        vec![
+            (0, WeightedTransition::new('a', 2, 1)),
+            (1, WeightedTransition::new('b', 3, 3)),
+            (0, WeightedTransition::new('c', 10, 3)),
+            (0, WeightedTransition::new('d', 0, 2)),
+        ],
+        0,
+    )
+    .unwrap();
+
+    assert_eq!(
+        weighted.lightest_accepted(|&v| v),
+        Some((5, vec!['a', 'b']))
+    );
+    assert_eq!(weighted.lightest_accepted(|_| false), None);
+    assert_eq!(weighted.lightest_accepted(|_| true), Some((0, vec![])));
+
+    assert!(weighted.set_weight(0, &'c', 1));
+    assert!(!weighted.set_weight(2, &'c', 1));
+    assert_eq!(weighted.weight(0, &'c'), Some(1));
+    assert_eq!(weighted.lightest_accepted(|&v| v), Some((1, vec!['c'])));
+
+    // with equal weights, the shortest string wins
+    let unweighted = WeightedDFA::new(weighted.into_dfa(), 1);
+    assert_eq!(unweighted.lightest_accepted(|&v| v), Some((1, vec!['c'])));
+}
+
+#[test]
+fn test_lightest_accepted_per_transition() {
+    // 'x' costs differently out of the two states with equal values, and loops are free
+    let weighted = WeightedDFA::from_parts(
+        vec![false, false, false, true],
+        vec![
+            (0, WeightedTransition::new('a', 1, 1)),
+            (0, WeightedTransition::new('b', 1, 2)),
+            (1, WeightedTransition::new('x', 10, 3)),
+            (2, WeightedTransition::new('x', 2, 3)),
+            (2, WeightedTransition::new('y', 0, 2)),
+        ],
+        0,
+    )
+    .unwrap();
+    assert_eq!(
+        weighted.lightest_accepted(|&v| v),
+        Some((3, vec!['b', 'x']))
+    );
+
+    let conflicting = WeightedDFA::from_parts(
+        vec![true],
+        vec![
+            (0, WeightedTransition::new('x', 1, 0)),
+            (0, WeightedTransition::new('x', 2, 0)),
+        ],
+        0,
+    );
+    assert_eq!(
+        conflicting.unwrap_err(),
+        BuildError::MismatchedAnnotations(0)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_weighted_dfa_serde_round_trip() {
+    let weighted = WeightedDFA::from_parts(
+        vec![false, true],
+        vec![
+            (0, WeightedTransition::new('a', 4, 1)),
+            (0, WeightedTransition::new('b', 1, 0)),
+        ],
+        0,
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&weighted).unwrap();
+    let back: WeightedDFA<char, bool> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.lightest_accepted(|&v| v), Some((4, vec!['a'])));
+
+    let dfa = r#"{"values":[true],"transitions":[{"a":0}],"start":0}"#;
+    for weights in [r#"[]"#, r#"[{}]"#, r#"[{"b":1}]"#, r#"[{"a":1,"b":1}]"#] {
+        let json = format!(r#"{{"dfa":{},"weights":{}}}"#, dfa, weights);
+        assert!(serde_json::from_str::<WeightedDFA<char, bool>>(&json).is_err());
+    }
+}